@@ -0,0 +1,571 @@
+//! On-disk cache of the server list, to avoid downloading it every time.
+//!
+//! The raw response of the API is stored, together with the moment it was downloaded. The cached
+//! list is used as long as it is younger than a given time-to-live (TTL).
+//!
+//! When the API provides an `ETag` or `Last-Modified` header, these are stored as well. An expired
+//! list is then revalidated using a conditional request, so the list is only downloaded again when
+//! it has changed.
+//!
+//! The URL the list was downloaded from is stored too, including the requested fields. A list
+//! cached for another URL, such as a mirror or a list with fewer fields, is not used.
+use api::{self, ApiConfig, ApiError, Failure, StatusError};
+use reqwest;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde_json;
+use servers::Servers;
+use std;
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The file containing the cached API response.
+const DATA_FILE: &str = "servers.json";
+/// The file containing the metadata of the cached API response.
+const META_FILE: &str = "meta.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+/// Information about the cached API response.
+struct CacheMeta {
+    /// The URL the response was downloaded from.
+    #[serde(default)]
+    url: String,
+    /// The moment the response was downloaded or revalidated, in seconds since the Unix epoch.
+    fetched_at: u64,
+    /// The validators sent by the API along with the response.
+    #[serde(default)]
+    validators: Validators,
+}
+
+impl CacheMeta {
+    /// Returns whether the response was downloaded at most `ttl` ago. A timestamp in the future is
+    /// treated as expired.
+    fn is_fresh(&self, ttl: Duration) -> bool {
+        now()
+            .checked_sub(self.fetched_at)
+            .map_or(false, |age| Duration::from_secs(age) <= ttl)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+/// The headers used to check whether a cached response is still up to date.
+struct Validators {
+    /// The value of the `ETag` header.
+    etag: Option<String>,
+    /// The value of the `Last-Modified` header.
+    last_modified: Option<String>,
+}
+
+/// The result of a conditional request to the API.
+enum Fetched {
+    /// The response has changed. Contains the new body and its validators.
+    Modified(String, Validators),
+    /// The cached response is still up to date.
+    NotModified,
+}
+
+/// A directory in which the list of servers is cached. The cached servers are sorted by domain,
+/// like the ones downloaded by [Servers::from_api](../servers/struct.Servers.html#method.from_api).
+///
+/// # Example
+///
+/// ```
+/// use nordselect::cache::Cache;
+/// use std::time::Duration;
+///
+/// let cache = Cache::new(Cache::default_dir().unwrap());
+/// // Download the servers at most once every 5 minutes.
+/// let data = cache.load(Duration::from_secs(300), false).unwrap();
+/// assert!(data.perfect_server().is_some());
+/// ```
+pub struct Cache {
+    /// The directory containing the cached files.
+    dir: PathBuf,
+    /// Whether to keep the JSON object of every server in `Server::raw`.
+    keep_raw: bool,
+}
+
+/// Ways to construct a Cache.
+impl Cache {
+    /// Creates a Cache storing its files in the given directory. The directory is created when
+    /// the cache is written for the first time.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Cache {
+        Cache {
+            dir: dir.into(),
+            keep_raw: false,
+        }
+    }
+
+    /// Returns the default cache directory, following the
+    /// [XDG Base Directory Specification](https://specifications.freedesktop.org/basedir-spec/latest/):
+    /// `$XDG_CACHE_HOME/nordselect`, or `~/.cache/nordselect` when that variable is not set.
+    ///
+    /// Returns `None` if neither `XDG_CACHE_HOME` nor `HOME` is set.
+    pub fn default_dir() -> Option<PathBuf> {
+        let base = match env::var_os("XDG_CACHE_HOME") {
+            Some(ref dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".cache"),
+        };
+        Some(base.join("nordselect"))
+    }
+
+    /// Sets whether to keep the JSON object of every server in `Server::raw`.
+    pub(crate) fn keep_raw(mut self, keep_raw: bool) -> Cache {
+        self.keep_raw = keep_raw;
+        self
+    }
+}
+
+impl Cache {
+    /// Returns the cached list of servers if it is younger than `ttl`. Otherwise, the list is
+    /// downloaded from the API and cached.
+    ///
+    /// When `force_refresh` is set, the list is always downloaded. Cached files that cannot be
+    /// read or parsed are ignored. Failing to write the cache does not return an error, as the
+    /// downloaded list can still be used.
+    pub fn load(
+        &self,
+        ttl: Duration,
+        force_refresh: bool,
+    ) -> Result<Servers, Box<dyn std::error::Error>> {
+        Ok(self.load_with_config(&ApiConfig::default(), ttl, force_refresh)?)
+    }
+
+    /// Like [load](#method.load), but downloads the list using the given configuration.
+    pub(crate) fn load_with_config(
+        &self,
+        config: &ApiConfig,
+        ttl: Duration,
+        force_refresh: bool,
+    ) -> Result<Servers, ApiError> {
+        let url = config.url();
+        self.load_with(&url, ttl, force_refresh, |validators| {
+            let client = config.client()?;
+            api::with_retry_counted(&config.retry, || fetch_if_modified(&client, &url, validators))
+        })
+    }
+
+    /// Like [load](#method.load), but uses the given function to download the API response from
+    /// `url`. The function receives the validators of the cached response, if any, and returns the
+    /// response with the number of attempts that were made.
+    fn load_with<F>(
+        &self,
+        url: &str,
+        ttl: Duration,
+        force_refresh: bool,
+        mut fetch: F,
+    ) -> Result<Servers, ApiError>
+    where
+        F: FnMut(&Validators) -> Result<(Fetched, u32), ApiError>,
+    {
+        // The cached list is only parsed when it is used, not when it is about to be replaced.
+        let meta = self.read_meta(url);
+        if let Some(ref meta) = meta {
+            if meta.is_fresh(ttl) && !force_refresh {
+                if let Some(servers) = self.read_servers(meta) {
+                    return Ok(servers);
+                }
+            }
+        }
+
+        let no_validators = Validators::default();
+        let validators = meta
+            .as_ref()
+            .map_or(&no_validators, |meta| &meta.validators);
+        let (text, validators, attempts) = match fetch(validators)? {
+            (Fetched::Modified(text, validators), attempts) => (text, validators, attempts),
+            (Fetched::NotModified, _) => {
+                let cached = meta.and_then(|meta| {
+                    let servers = self.read_servers(&meta)?;
+                    Some((meta, servers))
+                });
+                if let Some((meta, mut servers)) = cached {
+                    // The cached list was just confirmed to be up to date.
+                    servers.fetched_at = SystemTime::now();
+                    let _ = self.write_meta(url, meta.validators);
+                    return Ok(servers);
+                }
+                // The cached body is gone or corrupt, so download the list unconditionally.
+                match fetch(&no_validators)? {
+                    (Fetched::Modified(text, validators), attempts) => (text, validators, attempts),
+                    (Fetched::NotModified, attempts) => {
+                        return Err(ApiError::Status {
+                            attempts,
+                            error: StatusError::new(StatusCode::NOT_MODIFIED, String::new()),
+                        });
+                    }
+                }
+            }
+        };
+        let servers = Servers::from_json_reader_with(text.as_bytes(), self.keep_raw)
+            .map_err(|error| ApiError::Parse { attempts, error })?
+            .by_domain();
+        // The downloaded list is still usable if the cache could not be written.
+        let _ = self.write(url, &text, validators);
+        Ok(servers)
+    }
+
+    /// Returns the metadata of the cached API response, if it exists, can be parsed and was
+    /// downloaded from the given URL.
+    fn read_meta(&self, url: &str) -> Option<CacheMeta> {
+        let meta = fs::read_to_string(self.dir.join(META_FILE)).ok()?;
+        let meta: CacheMeta = serde_json::from_str(&meta).ok()?;
+        if meta.url != url {
+            return None;
+        }
+        Some(meta)
+    }
+
+    /// Returns the cached list of servers with the given metadata, if it exists and can be
+    /// parsed.
+    fn read_servers(&self, meta: &CacheMeta) -> Option<Servers> {
+        let data = File::open(self.dir.join(DATA_FILE)).ok()?;
+        let mut servers = Servers::from_json_reader_with(data, self.keep_raw).ok()?.by_domain();
+        servers.fetched_at = UNIX_EPOCH + Duration::from_secs(meta.fetched_at);
+        Some(servers)
+    }
+
+    /// Writes the given API response from `url` and its validators to the cache.
+    fn write(&self, url: &str, text: &str, validators: Validators) -> std::io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        write_atomic(&self.dir.join(DATA_FILE), text)?;
+        // The metadata is written last, so it never describes a partially written response.
+        self.write_meta(url, validators)
+    }
+
+    /// Marks the cached API response from `url` as downloaded now, with the given validators.
+    fn write_meta(&self, url: &str, validators: Validators) -> std::io::Result<()> {
+        let meta = CacheMeta {
+            url: url.to_string(),
+            fetched_at: now(),
+            validators,
+        };
+        write_atomic(&self.dir.join(META_FILE), &serde_json::to_string(&meta)?)
+    }
+}
+
+/// Replaces the file at `path` with the given contents. The contents are written to a temporary
+/// file in the same directory first, which is then renamed, so readers never see a partially
+/// written file.
+fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(format!(".{}.tmp", std::process::id()));
+    let temp = path.with_file_name(name);
+    if let Err(error) = fs::write(&temp, contents).and_then(|_| fs::rename(&temp, path)) {
+        let _ = fs::remove_file(&temp);
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Downloads the given URL, unless it has not changed according to the given validators.
+/// Returns an error on failure or when the server does not respond with a successful status code.
+fn fetch_if_modified(
+    client: &reqwest::Client,
+    url: &str,
+    validators: &Validators,
+) -> Result<Fetched, Failure> {
+    let mut request = client.get(url);
+    if let Some(ref etag) = validators.etag {
+        request = request.header(IF_NONE_MATCH, etag.as_str());
+    }
+    if let Some(ref last_modified) = validators.last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified.as_str());
+    }
+
+    let mut response = request.send()?;
+    // A 304 to an unconditional request is unexpected, and reported as an unsuccessful status.
+    let conditional = validators.etag.is_some() || validators.last_modified.is_some();
+    if conditional && response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(Fetched::NotModified);
+    }
+    api::check_status(response.status(), &mut response)?;
+
+    let validators = {
+        let header = |value: Option<&reqwest::header::HeaderValue>| {
+            value
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+        Validators {
+            etag: header(response.headers().get(ETAG)),
+            last_modified: header(response.headers().get(LAST_MODIFIED)),
+        }
+    };
+    Ok(Fetched::Modified(response.text()?, validators))
+}
+
+/// Returns the current time, in seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use servers::API_URL;
+    use std::cell::Cell;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A response of the API with one server.
+    const FIXTURE: &str = r#"[{
+        "flag": "NL",
+        "domain": "nl1.nordvpn.com",
+        "load": 12,
+        "categories": [{"name": "Standard VPN servers"}],
+        "features": {
+            "ikev2": true, "openvpn_udp": true, "openvpn_tcp": true, "socks": false,
+            "proxy": false, "pptp": false, "l2tp": false, "openvpn_xor_udp": false,
+            "openvpn_xor_tcp": false, "proxy_cybersec": false, "proxy_ssl": false,
+            "proxy_ssl_cybersec": false, "wireguard_udp": false
+        }
+    }]"#;
+
+    /// Returns the fixture as a modified response without validators, downloaded in one attempt.
+    fn modified(_: &Validators) -> Result<(Fetched, u32), ApiError> {
+        Ok((Fetched::Modified(FIXTURE.to_string(), Validators::default()), 1))
+    }
+
+    /// Creates an empty cache in a temporary directory with the given name, made unique to this
+    /// process and call.
+    fn temp_cache(name: &str) -> Cache {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = env::temp_dir().join(format!(
+            "{}_{}_{}",
+            name,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        Cache::new(dir)
+    }
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn cache_hit() {
+        let cache = temp_cache("nordselect_cache_hit");
+        let fetches = Cell::new(0);
+        let fetch = |validators: &Validators| {
+            fetches.set(fetches.get() + 1);
+            modified(validators)
+        };
+
+        assert_eq!(cache.load_with(API_URL, TTL, false, &fetch).unwrap().servers.len(), 1);
+        assert_eq!(cache.load_with(API_URL, TTL, false, &fetch).unwrap().servers.len(), 1);
+        assert_eq!(fetches.get(), 1);
+
+        // Refreshing always fetches.
+        cache.load_with(API_URL, TTL, true, &fetch).unwrap();
+        assert_eq!(fetches.get(), 2);
+
+        // The temporary files were renamed into place.
+        let mut files: Vec<_> = fs::read_dir(&cache.dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec![META_FILE, DATA_FILE]);
+
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+
+    #[test]
+    fn cache_expired() {
+        let cache = temp_cache("nordselect_cache_expired");
+        cache.write(API_URL, "[]", Validators::default()).unwrap();
+        let meta = CacheMeta {
+            url: API_URL.to_string(),
+            fetched_at: now() - 120,
+            validators: Validators::default(),
+        };
+        fs::write(cache.dir.join(META_FILE), serde_json::to_string(&meta).unwrap()).unwrap();
+
+        let data = cache.load_with(API_URL, TTL, false, modified).unwrap();
+        assert_eq!(data.servers.len(), 1);
+
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+
+    #[test]
+    fn cache_corrupt() {
+        let cache = temp_cache("nordselect_cache_corrupt");
+        cache.write(API_URL, "[{\"truncated", Validators::default()).unwrap();
+
+        let data = cache.load_with(API_URL, TTL, false, modified).unwrap();
+        assert_eq!(data.servers.len(), 1);
+
+        // The corrupt data was replaced.
+        assert_eq!(fs::read_to_string(cache.dir.join(DATA_FILE)).unwrap(), FIXTURE);
+
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+
+    #[test]
+    fn fetch_error_without_cache() {
+        let cache = temp_cache("nordselect_cache_error");
+
+        assert!(cache
+            .load_with(API_URL, TTL, false, |_| {
+                Err(ApiError::Transport {
+                    attempts: 1,
+                    error: From::from("offline"),
+                })
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn invalid_response_attempts() {
+        let cache = temp_cache("nordselect_cache_invalid");
+        let result = cache.load_with(API_URL, TTL, false, |_| {
+            Ok((Fetched::Modified("<h1>".to_string(), Validators::default()), 2))
+        });
+        match result {
+            Err(ApiError::Parse { attempts: 2, .. }) => {}
+            other => panic!("expected a parse error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn not_modified() {
+        let cache = temp_cache("nordselect_cache_not_modified");
+        let validators = Validators {
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+        };
+        cache.write(API_URL, FIXTURE, validators.clone()).unwrap();
+
+        // Refreshing sends the stored validators, and keeps the cached list on a 304.
+        let data = cache
+            .load_with(API_URL, TTL, true, |sent| {
+                assert_eq!(sent, &validators);
+                Ok((Fetched::NotModified, 1))
+            })
+            .unwrap();
+        assert_eq!(data.servers.len(), 1);
+
+        // The validators are kept for the next request.
+        let meta = cache.read_meta(API_URL).unwrap();
+        assert_eq!(meta.validators, validators);
+
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+
+    #[test]
+    fn not_modified_without_cache() {
+        let cache = temp_cache("nordselect_cache_not_modified_empty");
+
+        let result = cache.load_with(API_URL, TTL, false, |sent| {
+            assert_eq!(sent, &Validators::default());
+            Ok((Fetched::NotModified, 1))
+        });
+        match result {
+            Err(ApiError::Status { ref error, .. }) => {
+                assert_eq!(error.status(), StatusCode::NOT_MODIFIED);
+            }
+            other => panic!("expected a status error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn not_modified_corrupt_cache() {
+        let cache = temp_cache("nordselect_cache_not_modified_corrupt");
+        let validators = Validators {
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+        };
+        cache.write(API_URL, "[{\"truncated", validators.clone()).unwrap();
+
+        // The cached body cannot be used, so the list is downloaded again without validators.
+        let mut sent = Vec::new();
+        let data = cache
+            .load_with(API_URL, TTL, true, |validators| {
+                sent.push(validators.clone());
+                if validators.etag.is_some() {
+                    Ok((Fetched::NotModified, 1))
+                } else {
+                    modified(validators)
+                }
+            })
+            .unwrap();
+        assert_eq!(data.servers.len(), 1);
+        assert_eq!(sent, vec![validators, Validators::default()]);
+
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+
+    #[test]
+    fn cached_fetched_at() {
+        let cache = temp_cache("nordselect_cache_fetched_at");
+        cache.write(API_URL, FIXTURE, Validators::default()).unwrap();
+        let meta = CacheMeta {
+            url: API_URL.to_string(),
+            fetched_at: now() - 30,
+            validators: Validators::default(),
+        };
+        fs::write(cache.dir.join(META_FILE), serde_json::to_string(&meta).unwrap()).unwrap();
+
+        // A cached list keeps the moment it was downloaded.
+        let data = cache.load_with(API_URL, TTL, false, modified).unwrap();
+        assert_eq!(data.fetched_at, UNIX_EPOCH + Duration::from_secs(meta.fetched_at));
+        assert!(data.age() >= Duration::from_secs(30));
+        assert!(data.is_stale(Duration::from_secs(10)));
+
+        // A revalidated list is up to date.
+        let data = cache
+            .load_with(API_URL, TTL, true, |_| Ok((Fetched::NotModified, 1)))
+            .unwrap();
+        assert!(!data.is_stale(Duration::from_secs(10)));
+
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+
+    #[test]
+    fn stale_body_not_parsed() {
+        let cache = temp_cache("nordselect_cache_stale_body");
+        let validators = Validators {
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+        };
+        // The body is only read when it is used, so its validators are sent regardless.
+        cache.write(API_URL, "[{\"truncated", validators.clone()).unwrap();
+
+        let data = cache
+            .load_with(API_URL, TTL, true, |sent| {
+                assert_eq!(sent, &validators);
+                modified(sent)
+            })
+            .unwrap();
+        assert_eq!(data.servers.len(), 1);
+
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+
+    #[test]
+    fn cache_per_url() {
+        let cache = temp_cache("nordselect_cache_per_url");
+        let minimal = ApiConfig::new().minimal_fields(true).url();
+        let fetches = Cell::new(0);
+        let fetch = |validators: &Validators| {
+            fetches.set(fetches.get() + 1);
+            modified(validators)
+        };
+
+        cache.load_with(API_URL, TTL, false, &fetch).unwrap();
+        // A list with fewer fields is not served from the full list, and the other way around.
+        cache.load_with(&minimal, TTL, false, &fetch).unwrap();
+        cache.load_with(API_URL, TTL, false, &fetch).unwrap();
+        assert_eq!(fetches.get(), 3);
+        cache.load_with(API_URL, TTL, false, &fetch).unwrap();
+        assert_eq!(fetches.get(), 3);
+
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+}
@@ -0,0 +1,161 @@
+//! Mapping of countries onto the continent they are located in.
+//!
+//! The mapping is done using a lookup table included in this crate, so no network access is
+//! required.
+use std;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The seven continents.
+pub enum Continent {
+    /// Africa
+    Africa,
+    /// Antarctica, including the sub-antarctic islands.
+    Antarctica,
+    /// Asia, including the Middle East.
+    Asia,
+    /// Europe
+    Europe,
+    /// North America, including Central America and the Caribbean.
+    NorthAmerica,
+    /// Oceania, including Australia.
+    Oceania,
+    /// South America
+    SouthAmerica,
+}
+
+impl Continent {
+    /// Returns the continent of the given country, noted according to
+    /// [ISO 3166-1 alpha-2](https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2). The code can be
+    /// uppercase or lowercase.
+    ///
+    /// Returns `None` if the country is not known.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::continents::Continent;
+    ///
+    /// assert_eq!(Continent::from_flag("NL"), Some(Continent::Europe));
+    /// assert_eq!(Continent::from_flag("br"), Some(Continent::SouthAmerica));
+    /// assert_eq!(Continent::from_flag("XX"), None);
+    /// ```
+    pub fn from_flag(flag: &str) -> Option<Continent> {
+        match flag.to_ascii_uppercase().as_ref() {
+            "DZ" | "AO" | "BJ" | "BW" | "BF" | "BI" | "CV" | "CM" | "CF" | "TD" | "KM" | "CG"
+            | "CD" | "CI" | "DJ" | "EG" | "GQ" | "ER" | "SZ" | "ET" | "GA" | "GM" | "GH" | "GN"
+            | "GW" | "KE" | "LS" | "LR" | "LY" | "MG" | "MW" | "ML" | "MR" | "MU" | "YT" | "MA"
+            | "MZ" | "NA" | "NE" | "NG" | "RE" | "RW" | "SH" | "ST" | "SN" | "SC" | "SL" | "SO"
+            | "ZA" | "SS" | "SD" | "TZ" | "TG" | "TN" | "UG" | "EH" | "ZM" | "ZW" => {
+                Some(Continent::Africa)
+            }
+            "AQ" | "BV" | "GS" | "HM" | "TF" => Some(Continent::Antarctica),
+            "AF" | "AM" | "AZ" | "BH" | "BD" | "BT" | "BN" | "KH" | "CN" | "CY" | "GE" | "HK"
+            | "IN" | "ID" | "IR" | "IQ" | "IL" | "JP" | "JO" | "KZ" | "KW" | "KG" | "LA" | "LB"
+            | "MO" | "MY" | "MV" | "MN" | "MM" | "NP" | "KP" | "OM" | "PK" | "PS" | "PH" | "QA"
+            | "SA" | "SG" | "KR" | "LK" | "SY" | "TW" | "TJ" | "TH" | "TL" | "TR" | "TM" | "AE"
+            | "UZ" | "VN" | "YE" | "IO" | "CC" | "CX" => Some(Continent::Asia),
+            "AX" | "AL" | "AD" | "AT" | "BY" | "BE" | "BA" | "BG" | "HR" | "CZ" | "DK" | "EE"
+            | "FO" | "FI" | "FR" | "DE" | "GI" | "GR" | "GG" | "VA" | "HU" | "IS" | "IE" | "IM"
+            | "IT" | "JE" | "XK" | "LV" | "LI" | "LT" | "LU" | "MT" | "MD" | "MC" | "ME" | "NL"
+            | "MK" | "NO" | "PL" | "PT" | "RO" | "RU" | "SM" | "RS" | "SK" | "SI" | "ES" | "SJ"
+            | "SE" | "CH" | "UA" | "GB" | "UK" => Some(Continent::Europe),
+            "AI" | "AG" | "AW" | "BS" | "BB" | "BZ" | "BM" | "BQ" | "VG" | "CA" | "KY" | "CR"
+            | "CU" | "CW" | "DM" | "DO" | "SV" | "GL" | "GD" | "GP" | "GT" | "HT" | "HN" | "JM"
+            | "MQ" | "MX" | "MS" | "NI" | "PA" | "PR" | "BL" | "KN" | "LC" | "MF" | "PM" | "VC"
+            | "SX" | "TT" | "TC" | "US" | "VI" | "UM" => Some(Continent::NorthAmerica),
+            "AS" | "AU" | "CK" | "FJ" | "PF" | "GU" | "KI" | "MH" | "FM" | "NR" | "NC" | "NZ"
+            | "NU" | "NF" | "MP" | "PW" | "PG" | "PN" | "WS" | "SB" | "TK" | "TO" | "TV" | "VU"
+            | "WF" => Some(Continent::Oceania),
+            "AR" | "BO" | "BR" | "CL" | "CO" | "EC" | "FK" | "GF" | "GY" | "PY" | "PE" | "SR"
+            | "UY" | "VE" => Some(Continent::SouthAmerica),
+            _ => None,
+        }
+    }
+
+    /// Returns all possible continent names, as accepted by `str::parse`. Useful to provide lists
+    /// to your users to choose from.
+    pub fn names() -> [&'static str; 7] {
+        [
+            "africa",
+            "antarctica",
+            "asia",
+            "europe",
+            "north_america",
+            "oceania",
+            "south_america",
+        ]
+    }
+}
+
+/// The error returned when parsing an unknown continent.
+#[derive(Debug, PartialEq)]
+pub struct ParseContinentError(String);
+
+impl fmt::Display for ParseContinentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown continent: \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for ParseContinentError {}
+
+impl FromStr for Continent {
+    type Err = ParseContinentError;
+
+    /// Parses the name of a continent. The name is case-insensitive and words can be separated
+    /// using spaces, dashes or underscores (`north america`, `North-America`, `north_america`).
+    fn from_str(name: &str) -> Result<Continent, ParseContinentError> {
+        let normalized: String = name
+            .trim()
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '_' && *c != '-')
+            .collect::<String>()
+            .to_lowercase();
+        match normalized.as_ref() {
+            "africa" => Ok(Continent::Africa),
+            "antarctica" => Ok(Continent::Antarctica),
+            "asia" => Ok(Continent::Asia),
+            "europe" => Ok(Continent::Europe),
+            "northamerica" => Ok(Continent::NorthAmerica),
+            "oceania" => Ok(Continent::Oceania),
+            "southamerica" => Ok(Continent::SouthAmerica),
+            _ => Err(ParseContinentError(name.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_country_per_continent() {
+        assert_eq!(Continent::from_flag("EG"), Some(Continent::Africa));
+        assert_eq!(Continent::from_flag("AQ"), Some(Continent::Antarctica));
+        assert_eq!(Continent::from_flag("JP"), Some(Continent::Asia));
+        assert_eq!(Continent::from_flag("NL"), Some(Continent::Europe));
+        assert_eq!(Continent::from_flag("US"), Some(Continent::NorthAmerica));
+        assert_eq!(Continent::from_flag("AU"), Some(Continent::Oceania));
+        assert_eq!(Continent::from_flag("AR"), Some(Continent::SouthAmerica));
+    }
+
+    #[test]
+    fn unknown_country() {
+        assert_eq!(Continent::from_flag("XX"), None);
+        assert_eq!(Continent::from_flag(""), None);
+        assert_eq!(Continent::from_flag("EU"), None);
+    }
+
+    #[test]
+    fn parse_continents() {
+        for name in Continent::names().iter() {
+            assert!(name.parse::<Continent>().is_ok());
+        }
+        assert_eq!("Europe".parse::<Continent>(), Ok(Continent::Europe));
+        assert_eq!("north america".parse::<Continent>(), Ok(Continent::NorthAmerica));
+        assert_eq!("South-America".parse::<Continent>(), Ok(Continent::SouthAmerica));
+        assert!("atlantis".parse::<Continent>().is_err());
+    }
+}
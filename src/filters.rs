@@ -1,14 +1,131 @@
 //! The filters module consists of the Filter trait (used to implement filters) and several common inplementations of it.
 
-use super::{Protocol, Server, ServerCategory};
+use super::continents::Continent;
+pub use super::regions::Region;
+use super::sorters::PingSorter;
+use super::{Protocol, Server, ServerCategory, Servers};
+use regex::{self, Regex};
 use std::collections::HashSet;
+use std::fmt;
+use std::io;
 use std::iter::FromIterator;
+use std::path::Path;
+use std::str::FromStr;
 
 /// Way to reduce the amount of available servers.
 pub trait Filter {
     /// Returns whether this server fullfills the needs of the Filter. When false, the given server
     /// should be removed from the set.
     fn filter(&self, &Server) -> bool;
+
+    /// Returns a short description of this Filter, such as `CountryFilter(NL)`. Useful to explain
+    /// which filter removed the servers, e.g. in combination with
+    /// [Servers::filter_verbose](../servers/struct.Servers.html#method.filter_verbose).
+    fn describe(&self) -> String {
+        String::from("Filter")
+    }
+}
+
+/// Joins the items of a set in alphabetical order, to describe it deterministically.
+fn describe_set(set: &HashSet<String>) -> String {
+    let mut items: Vec<&str> = set.iter().map(|item| item.as_str()).collect();
+    items.sort_unstable();
+    items.join(",")
+}
+
+/// Describes a list of filters, joined by the given operator.
+fn describe_filters(filters: &[Box<dyn Filter>], operator: &str) -> String {
+    let descriptions: Vec<String> = filters.iter().map(|filter| filter.describe()).collect();
+    descriptions.join(operator)
+}
+
+/// The error returned when a token does not represent a known filter.
+#[derive(Debug, PartialEq)]
+pub struct ParseFilterError {
+    /// The unknown token, as given.
+    pub token: String,
+}
+
+impl fmt::Display for ParseFilterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown filter: \"{}\"", self.token)
+    }
+}
+
+impl std::error::Error for ParseFilterError {}
+
+/// Returns the category represented by the given token (`standard`, `p2p`, `double`,
+/// `dedicated`, `tor` or `obfuscated`), case-insensitively.
+pub fn category_from_str(token: &str) -> Option<ServerCategory> {
+    match token.to_lowercase().as_ref() {
+        "p2p" => Some(ServerCategory::P2P),
+        "standard" => Some(ServerCategory::Standard),
+        "double" => Some(ServerCategory::Double),
+        "dedicated" => Some(ServerCategory::Dedicated),
+        "tor" => Some(ServerCategory::Tor),
+        "obfuscated" => Some(ServerCategory::Obfuscated),
+        _ => None,
+    }
+}
+
+/// Returns the protocol represented by the given token (e.g. `tcp`, `udp` or `ikev2`),
+/// case-insensitively. WireGuard is represented by `wg_udp`, `wireguard` or `nordlynx`.
+pub fn protocol_from_str(token: &str) -> Option<Protocol> {
+    match token.to_lowercase().as_ref() {
+        "tcp" => Some(Protocol::Tcp),
+        "udp" => Some(Protocol::Udp),
+        "pptp" => Some(Protocol::Pptp),
+        "l2tp" => Some(Protocol::L2tp),
+        "tcp_xor" => Some(Protocol::OpenVPNXTcp),
+        "udp_xor" => Some(Protocol::OpenVPNXUdp),
+        "socks" => Some(Protocol::Socks),
+        "cybersecproxy" => Some(Protocol::CyberSecProxy),
+        "sslproxy" => Some(Protocol::SslProxy),
+        "cybersecsslproxy" => Some(Protocol::CyberSecSslProxy),
+        "proxy" => Some(Protocol::Proxy),
+        "wg_udp" | "wireguard" | "nordlynx" => Some(Protocol::WireGuardUdp),
+        "ikev2" => Some(Protocol::Ikev2),
+        _ => None,
+    }
+}
+
+/// Parses a filter from a token, as used by the CLI. Tokens are case-insensitive.
+///
+/// Categories (see [category_from_str](fn.category_from_str.html)) are tried first, followed by
+/// protocols (see [protocol_from_str](fn.protocol_from_str.html)) and countries. Countries are
+/// only accepted if they are in `known_flags`, which usually are the
+/// [flags](../servers/struct.Servers.html#method.flags) of the servers.
+///
+/// # Examples
+///
+/// ```
+/// use nordselect::Servers;
+/// use nordselect::filters;
+/// let mut data = Servers::dummy_data();
+///
+/// let filter = filters::from_str("BE", &data.flags()).unwrap();
+/// data.filter(filter.as_ref());
+/// assert_eq!(data.perfect_server().unwrap().flag, "BE");
+///
+/// assert!(filters::from_str("belgium", &data.flags()).is_err());
+/// ```
+pub fn from_str(
+    token: &str,
+    known_flags: &HashSet<&str>,
+) -> Result<Box<dyn Filter>, ParseFilterError> {
+    if let Some(category) = category_from_str(token) {
+        return Ok(Box::new(CategoryFilter::from(category)));
+    }
+    if let Some(protocol) = protocol_from_str(token) {
+        return Ok(Box::new(ProtocolFilter::from(protocol)));
+    }
+    let flag = token.trim().to_ascii_uppercase();
+    if known_flags.contains(flag.as_str()) {
+        return Ok(Box::new(CountryFilter::from(flag.as_str())));
+    }
+    Err(ParseFilterError {
+        token: token.to_string(),
+    })
 }
 
 /// Filter to only use servers from one specific country.
@@ -39,112 +156,26 @@ impl CountryFilter {
         note = "Inefficient, use the From-trait implementation instead"
     )]
     pub fn from_code(countrycode: String) -> CountryFilter {
-        CountryFilter {
-            country: countrycode.to_ascii_uppercase(),
-        }
+        CountryFilter::from(countrycode.as_str())
     }
 }
 
 impl Filter for CountryFilter {
     fn filter(&self, server: &Server) -> bool {
-        self.country == server.flag
+        self.country.eq_ignore_ascii_case(&server.flag)
+    }
+
+    fn describe(&self) -> String {
+        format!("CountryFilter({})", self.country)
     }
 }
 
+/// Creates a CountryFilter from the given country. Surrounding whitespace is ignored and the
+/// country is matched case-insensitively.
 impl<'a> From<&'a str> for CountryFilter {
     fn from(countrycode: &str) -> CountryFilter {
         CountryFilter {
-            country: countrycode.to_ascii_uppercase(),
-        }
-    }
-}
-
-#[derive(Debug, PartialEq)]
-pub enum Region {
-    /// The [European Union](https://en.wikipedia.org/wiki/European_Union), consisting of 27 countries.
-    ///
-    /// Because of the Brexit, the United Kingdom is not included in this region
-    EuropeanUnion,
-    /// The European Economic Area, consisting of the European Union, Norway, Lichtenstein and Iceland.
-    EuropeanEconomicArea,
-    /// The Benelux consists of Belgium, The Netherlands and Luxembourgh
-    Benelux,
-    /// [5 eyes programme countries](https://en.wikipedia.org/wiki/Five_Eyes)
-    FiveEyes,
-    /// [6 eyes programme countries.](https://en.wikipedia.org/wiki/Five_Eyes#Other_international_cooperatives)
-    SixEyes,
-    /// [9 eyes programme countries.](https://en.wikipedia.org/wiki/Five_Eyes#Other_international_cooperatives)
-    NineEyes,
-    /// [14 eyes programme countries.](https://en.wikipedia.org/wiki/Five_Eyes#Other_international_cooperatives)
-    FourteenEyes,
-}
-
-impl Region {
-    /// Tries to create a Region from a string slice. Returns a Region if there's one represented
-    /// by your str slice. Returns None otherwise.
-    ///
-    /// The provided str slice should be **uppercase**!
-    pub fn from_str(region_short: &str) -> Option<Region> {
-        match region_short {
-            "EU" | "ЕЮ" => Some(Region::EuropeanUnion),
-            "EEA" => Some(Region::EuropeanEconomicArea),
-            "BENELUX" => Some(Region::Benelux),
-            "5E" => Some(Region::FiveEyes),
-            "6E" => Some(Region::SixEyes),
-            "9E" => Some(Region::NineEyes),
-            "14E" => Some(Region::FourteenEyes),
-            _ => None,
-        }
-    }
-
-    /// Returns all possible region codes with their respective meanings in human readable form.
-    /// Useful to provide lists to your users to choose from.
-    ///
-    /// Using a value from index 0 of the tuple will guaranteed give a Some when calling `[from_str](#method_from_str)`
-    pub fn from_str_options() -> [(&'static str, &'static str); 8] {
-        [
-            ("EU", "The European Union"),
-            ("ЕЮ", "The European Union (Cyrillic notation)"),
-            ("EEA", "The European Economic Area"),
-            ("BENELUX", "Countries of the Benelux"),
-            ("5E", "Countries involved in the Five Eyes programme."),
-            ("6E", "Countries involved in the Six Eyes programme."),
-            ("9E", "Countries involved in the Nine Eyes programme."),
-            ("14E", "Countries involved in the Fourteen Eyes programme."),
-        ]
-    }
-
-    /// Returns the main short notation for a given Region.
-    pub fn short(&self) -> &'static str {
-        match self {
-            Region::EuropeanUnion => "EU",
-            Region::EuropeanEconomicArea => "EEA",
-            Region::Benelux => "BENELUX",
-            Region::FiveEyes => "5E",
-            Region::SixEyes => "6E",
-            Region::NineEyes => "9E",
-            Region::FourteenEyes => "14E",
-        }
-    }
-
-    pub fn countries(&self) -> Vec<&str> {
-        match self {
-            Region::EuropeanEconomicArea => vec![
-                "AT", "BE", "BG", "HR", "CY", "CZ", "DK", "EE", "FI", "FR", "DE", "GR", "HU", "IE",
-                "IT", "LV", "LT", "LU", "MT", "NL", "PL", "PT", "RO", "SK", "SI", "ES", "SE", "NO",
-                "LI", "IS",
-            ],
-            Region::EuropeanUnion => vec![
-                "AT", "BE", "BG", "HR", "CY", "CZ", "DK", "EE", "FI", "FR", "DE", "GR", "HU", "IE",
-                "IT", "LV", "LT", "LU", "MT", "NL", "PL", "PT", "RO", "SK", "SI", "ES", "SE",
-            ],
-            Region::Benelux => vec!["BE", "LU", "NL"],
-            Region::FiveEyes => vec!["AU", "CA", "NZ", "GB", "US"],
-            Region::SixEyes => vec!["AU", "CA", "FR", "NZ", "GB", "US"],
-            Region::NineEyes => vec!["AU", "CA", "DK", "FR", "NL", "NO", "NZ", "GB", "US"],
-            Region::FourteenEyes => vec![
-                "AU", "BE", "CA", "DE", "DK", "ES", "FR", "IT", "NL", "NO", "NZ", "GB", "SE", "US",
-            ],
+            country: countrycode.trim().to_ascii_uppercase(),
         }
     }
 }
@@ -152,7 +183,8 @@ impl Region {
 /// Filter that keeps servers from any of the provided countries.
 ///
 /// This struct can be build from your own list of countries, or it can be used with one of the
-/// provided regions. To see the available regions, use [CountriesFilter::available_regions()](#method.available_regions)
+/// provided regions using `CountriesFilter::from(Region::Asia)`. To see the available regions, use
+/// [Region::from_str_options()](../regions/enum.Region.html#method.from_str_options)
 ///
 /// # Examples
 /// ```
@@ -249,255 +281,1345 @@ impl From<HashSet<String>> for CountriesFilter {
     }
 }
 
+/// The error returned when a list of countries contains an invalid country.
+#[derive(Debug, PartialEq)]
+pub struct ParseCountriesError {
+    /// The invalid country, as given.
+    pub token: String,
+}
+
+impl fmt::Display for ParseCountriesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid country \"{}\": expected a two-letter country code",
+            self.token
+        )
+    }
+}
+
+impl std::error::Error for ParseCountriesError {}
+
+/// Parses a comma-separated list of countries, such as `"nl,de,be"`.
+///
+/// Countries should be noted according to
+/// [ISO 3166-1 alpha-2](https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2), but can be uppercase or
+/// lowercase. Whitespace and empty entries are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use nordselect::filters::CountriesFilter;
+///
+/// assert!("nl, de,be,".parse::<CountriesFilter>().is_ok());
+/// assert!("nl,netherlands".parse::<CountriesFilter>().is_err());
+/// ```
+impl FromStr for CountriesFilter {
+    type Err = ParseCountriesError;
+
+    fn from_str(list: &str) -> Result<CountriesFilter, ParseCountriesError> {
+        let mut countries = HashSet::new();
+        for token in list.split(',').map(|token| token.trim()) {
+            if token.is_empty() {
+                continue;
+            }
+            if token.len() != 2 || !token.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(ParseCountriesError {
+                    token: token.to_string(),
+                });
+            }
+            countries.insert(token.to_ascii_uppercase());
+        }
+        Ok(CountriesFilter { countries })
+    }
+}
+
 impl Filter for CountriesFilter {
     fn filter(&self, server: &Server) -> bool {
         self.countries.contains(&server.flag)
     }
+
+    fn describe(&self) -> String {
+        format!("CountriesFilter({})", describe_set(&self.countries))
+    }
 }
 
-/// Filter that keeps only servers that accept a specific protocol.
+/// Filter that keeps servers from one region: a built-in [Region](../regions/enum.Region.html) or
+/// your own set of countries. Countries are compared case-insensitively.
 ///
-/// # Example
+/// # Examples
 ///
 /// ```
 /// use nordselect::Servers;
-/// use nordselect::Protocol;
-/// use nordselect::filters::ProtocolFilter;
-/// let mut data = Servers::dummy_data();
+/// use nordselect::filters::{Region, RegionFilter};
 ///
-/// // Filter on the TCP protocol
-/// data.filter(&ProtocolFilter::from(Protocol::Tcp));
+/// let mut data = Servers::dummy_data();
+/// data.filter(&RegionFilter::from(Region::EuropeanUnion));
 ///
-/// assert!(data.perfect_server().is_some());
+/// let flag = data.perfect_server().unwrap().flag;
+/// assert!(Region::EuropeanUnion.countries().contains(&flag.as_str()));
 /// ```
-pub struct ProtocolFilter {
-    /// The protocol that should be filtered against.
-    protocol: Protocol,
+pub struct RegionFilter {
+    /// The built-in region, if the filter was created from one.
+    region: Option<Region>,
+    /// Uppercase countries of the region.
+    countries: HashSet<String>,
 }
 
-impl From<Protocol> for ProtocolFilter {
-    fn from(protocol: Protocol) -> ProtocolFilter {
-        ProtocolFilter { protocol }
+impl From<Region> for RegionFilter {
+    fn from(region: Region) -> RegionFilter {
+        RegionFilter {
+            region: Some(region),
+            countries: region.countries().into_iter().map(String::from).collect(),
+        }
     }
 }
 
-impl Filter for ProtocolFilter {
+/// Creates a RegionFilter from your own set of countries, noted according to
+/// [ISO 3166-1 alpha-2](https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2).
+impl From<HashSet<String>> for RegionFilter {
+    fn from(countries: HashSet<String>) -> RegionFilter {
+        RegionFilter {
+            region: None,
+            countries: countries
+                .iter()
+                .map(|country| country.trim().to_ascii_uppercase())
+                .collect(),
+        }
+    }
+}
+
+impl Filter for RegionFilter {
     fn filter(&self, server: &Server) -> bool {
-        match self.protocol {
-            Protocol::Tcp => server.features.openvpn_tcp,
-            Protocol::Udp => server.features.openvpn_udp,
-            Protocol::Pptp => server.features.pptp,
-            Protocol::L2tp => server.features.l2tp,
-            Protocol::OpenVPNXTcp => server.features.openvpn_tcp,
-            Protocol::OpenVPNXUdp => server.features.openvpn_udp,
-            Protocol::Socks => server.features.socks,
-            Protocol::CyberSecProxy => server.features.proxy_cybersec,
-            Protocol::SslProxy => server.features.proxy_ssl,
-            Protocol::CyberSecSslProxy => server.features.proxy_ssl_cybersec,
-            Protocol::Proxy => server.features.proxy,
-            Protocol::WireGuardUdp => server.features.wireguard_udp,
+        self.countries.contains(&server.flag.to_ascii_uppercase())
+    }
+
+    fn describe(&self) -> String {
+        match self.region {
+            Some(region) => format!("RegionFilter({})", region.short()),
+            None => format!("RegionFilter({})", describe_set(&self.countries)),
         }
     }
 }
 
-/// Filter that keeps servers with less or equal load compared to a provided value.
+/// Filter that keeps servers located on a specific continent.
+///
+/// Servers with a country that is unknown to the
+/// [continent lookup](../continents/enum.Continent.html#method.from_flag) are removed.
 ///
 /// # Example
 ///
 /// ```
 /// use nordselect::Servers;
-/// use nordselect::filters::LoadFilter;
-/// let mut data = Servers::dummy_data();
+/// use nordselect::continents::Continent;
+/// use nordselect::filters::ContinentFilter;
 ///
-/// // Filter on 10% load or less.
-/// data.filter(&LoadFilter::from(10));
+/// let mut data = Servers::dummy_data();
+/// data.filter(&ContinentFilter::from(Continent::Europe));
 ///
-/// assert!(data.perfect_server().is_some());
+/// let flag = data.perfect_server().unwrap().flag;
+/// assert_eq!(Continent::from_flag(&flag), Some(Continent::Europe));
 /// ```
-pub struct LoadFilter {
-    /// The maximal allowed load.
-    load: u8,
+pub struct ContinentFilter {
+    /// The continent servers should be located on.
+    continent: Continent,
 }
 
-impl From<u8> for LoadFilter {
-    fn from(load: u8) -> LoadFilter {
-        LoadFilter { load }
+impl From<Continent> for ContinentFilter {
+    fn from(continent: Continent) -> ContinentFilter {
+        ContinentFilter { continent }
     }
 }
 
-impl Filter for LoadFilter {
+impl Filter for ContinentFilter {
     fn filter(&self, server: &Server) -> bool {
-        server.load.cmp(&self.load) != std::cmp::Ordering::Greater
+        Continent::from_flag(&server.flag) == Some(self.continent)
+    }
+
+    fn describe(&self) -> String {
+        format!("ContinentFilter({:?})", self.continent)
     }
 }
 
-/// Filter that contains multiple Filter instances. This could be more efficient, as only servers
-/// fullfilling all requirements are kept.
+/// Filter that removes servers from any of the provided countries.
 ///
-/// Logically, this should be viewed as an AND-gate, as every `Filter` should allow the server to
-/// be kept.
-pub struct CombinedFilter {
-    // The actual filters
-    filters: Vec<Box<dyn Filter>>,
+/// Countries are compared case-insensitively. This keeps exactly the servers that would be removed
+/// by a `CountriesFilter` with the same countries.
+///
+/// # Examples
+/// ```
+/// use nordselect::Servers;
+/// use nordselect::filters::ExcludeCountriesFilter;
+/// use std::collections::HashSet;
+///
+/// let mut data = Servers::dummy_data();
+///
+/// let mut countries = HashSet::new();
+/// countries.insert("us".to_string());
+/// countries.insert("GB".to_string());
+/// data.filter(&ExcludeCountriesFilter::from(countries));
+///
+/// let flag = data.perfect_server().unwrap().flag;
+/// assert!(flag != "US" && flag != "GB");
+/// ```
+pub struct ExcludeCountriesFilter {
+    /// Countries which are not allowed, in uppercase.
+    countries: HashSet<String>,
 }
 
-/// Ways to construct `CombinedFilters`.
-impl CombinedFilter {
-    /// Builds a new `CombinedFilter`.
-    pub fn new() -> CombinedFilter {
-        CombinedFilter {
-            filters: Vec::new(),
+impl From<HashSet<String>> for ExcludeCountriesFilter {
+    fn from(countries: HashSet<String>) -> ExcludeCountriesFilter {
+        ExcludeCountriesFilter {
+            countries: HashSet::from_iter(
+                countries
+                    .into_iter()
+                    .map(|country| country.to_ascii_uppercase()),
+            ),
         }
     }
+}
 
-    /// Builds a new `CombinedFilter` with the given capacity.
-    pub fn with_capacity(capacity: usize) -> CombinedFilter {
-        CombinedFilter {
-            filters: Vec::with_capacity(capacity),
-        }
+impl Filter for ExcludeCountriesFilter {
+    fn filter(&self, server: &Server) -> bool {
+        !self.countries.contains(&server.flag.to_ascii_uppercase())
     }
-}
 
-impl From<Vec<Box<dyn Filter>>> for CombinedFilter {
-    fn from(filters: Vec<Box<dyn Filter>>) -> CombinedFilter {
-        CombinedFilter { filters }
+    fn describe(&self) -> String {
+        format!("ExcludeCountriesFilter({})", describe_set(&self.countries))
     }
 }
 
-impl CombinedFilter {
-    /// Adds a new filter
-    pub fn add_filter(&mut self, filter: Box<dyn Filter>) {
-        self.filters.push(filter);
+/// Filter that keeps servers located in a specific city. Cities are compared case-insensitively.
+///
+/// Servers of which the city is unknown are removed.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::Servers;
+/// use nordselect::filters::CityFilter;
+/// let mut data = Servers::dummy_data();
+///
+/// data.filter(&CityFilter::from("Amsterdam"));
+///
+/// if let Some(server) = data.perfect_server() {
+///     assert_eq!(server.city.unwrap(), "Amsterdam");
+/// }
+/// ```
+pub struct CityFilter {
+    /// The city servers should be located in, in lowercase.
+    city: String,
+}
+
+impl<'a> From<&'a str> for CityFilter {
+    fn from(city: &str) -> CityFilter {
+        CityFilter {
+            city: city.trim().to_lowercase(),
+        }
     }
 }
 
-impl Filter for CombinedFilter {
+impl Filter for CityFilter {
     fn filter(&self, server: &Server) -> bool {
-        self.filters
-            .iter()
-            // Sorry for the confusing line of Rust code.
-            .filter(|filter| filter.filter(server))
-            .next()
-            .is_some()
+        match server.city {
+            Some(ref city) => city.to_lowercase() == self.city,
+            None => false,
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("CityFilter({})", self.city)
     }
 }
 
-/// Filter the Servers using a given category.
+/// Filter that keeps only servers that accept a specific protocol.
 ///
 /// # Example
 ///
 /// ```
-/// use nordselect::{Servers, ServerCategory};
-/// use nordselect::filters::CategoryFilter;
+/// use nordselect::Servers;
+/// use nordselect::Protocol;
+/// use nordselect::filters::ProtocolFilter;
 /// let mut data = Servers::dummy_data();
 ///
-/// // Filter on Standard servers.
-/// data.filter(&CategoryFilter::from(ServerCategory::Standard));
+/// // Filter on the TCP protocol
+/// data.filter(&ProtocolFilter::from(Protocol::Tcp));
 ///
 /// assert!(data.perfect_server().is_some());
 /// ```
-pub struct CategoryFilter {
-    category: ServerCategory,
+pub struct ProtocolFilter {
+    /// The protocol that should be filtered against.
+    protocol: Protocol,
 }
 
-impl From<ServerCategory> for CategoryFilter {
-    fn from(category: ServerCategory) -> CategoryFilter {
-        CategoryFilter { category }
+impl From<Protocol> for ProtocolFilter {
+    fn from(protocol: Protocol) -> ProtocolFilter {
+        ProtocolFilter { protocol }
     }
 }
 
-impl Filter for CategoryFilter {
+impl Filter for ProtocolFilter {
     fn filter(&self, server: &Server) -> bool {
-        server.categories.contains(&self.category)
+        server.supports(self.protocol)
+    }
+
+    fn describe(&self) -> String {
+        format!("ProtocolFilter({:?})", self.protocol)
     }
 }
 
-/// Filter that negates the results of a given filter.
+/// Filter that keeps only servers that accept all of the given protocols.
+///
+/// An `AllProtocolsFilter` without protocols keeps every server.
 ///
 /// # Example
 ///
 /// ```
 /// use nordselect::Servers;
-/// use nordselect::filters::{CountryFilter, NegatingFilter};
-///
+/// use nordselect::Protocol;
+/// use nordselect::filters::AllProtocolsFilter;
 /// let mut data = Servers::dummy_data();
-/// data.filter(&NegatingFilter::new(CountryFilter::from("BE")));
 ///
-/// assert_ne!(data.perfect_server().unwrap().flag, "BE");
+/// // Filter on servers that support both UDP and TCP, to fall back on TCP when UDP is blocked.
+/// data.filter(&AllProtocolsFilter::from(vec![Protocol::Udp, Protocol::Tcp]));
+///
+/// let server = data.perfect_server().unwrap();
+/// assert!(server.features.openvpn_udp && server.features.openvpn_tcp);
 /// ```
-pub struct NegatingFilter(Box<dyn Filter>);
-
-impl NegatingFilter {
-    pub fn new(filter: impl Filter + 'static) -> Self {
-        Self(Box::new(filter))
-    }
+pub struct AllProtocolsFilter {
+    /// The protocols that should all be supported.
+    protocols: Vec<Protocol>,
 }
 
-impl From<Box<dyn Filter + 'static>> for NegatingFilter {
-    fn from(filter: Box<dyn Filter + 'static>) -> Self {
-        Self(filter)
+impl From<Vec<Protocol>> for AllProtocolsFilter {
+    fn from(protocols: Vec<Protocol>) -> AllProtocolsFilter {
+        AllProtocolsFilter { protocols }
     }
 }
 
-impl Filter for NegatingFilter {
+impl Filter for AllProtocolsFilter {
     fn filter(&self, server: &Server) -> bool {
-        !self.0.filter(server)
+        self.protocols
+            .iter()
+            .all(|&protocol| server.supports(protocol))
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::super::Servers;
-    use super::*;
 
-    #[test]
-    #[allow(deprecated)]
-    fn country_filter_simple_legacy() {
-        let mut data = Servers::dummy_data();
-
-        data.filter(&CountryFilter::from_code("sg".to_string()));
-
-        let server_opt = data.perfect_server();
-
-        assert!(server_opt.is_some());
-        assert_eq!(server_opt.unwrap().flag, "SG");
+    fn describe(&self) -> String {
+        format!("AllProtocolsFilter({:?})", self.protocols)
     }
+}
 
-    #[test]
-    #[allow(deprecated)]
-    fn country_filter_advanced_legacy() {
-        let mut data = Servers::dummy_data();
-
-        data.filter(&CountryFilter::from_code("Sg".to_string()));
-
-        let server_opt = data.perfect_server();
+/// Filter that keeps servers supporting at least one modern protocol: OpenVPN (UDP or TCP), IKEv2
+/// or WireGuard.
+///
+/// Servers that only support the insecure PPTP and L2TP protocols are removed.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::Servers;
+/// use nordselect::filters::ModernProtocolFilter;
+/// let mut data = Servers::dummy_data();
+///
+/// data.filter(&ModernProtocolFilter);
+///
+/// assert!(data.perfect_server().is_some());
+/// ```
+pub struct ModernProtocolFilter;
 
-        assert!(server_opt.is_some());
-        assert_eq!(server_opt.unwrap().flag, "SG");
+impl Filter for ModernProtocolFilter {
+    fn filter(&self, server: &Server) -> bool {
+        server.supports(Protocol::Udp)
+            || server.supports(Protocol::Tcp)
+            || server.supports(Protocol::Ikev2)
+            || server.supports(Protocol::WireGuardUdp)
     }
 
-    #[test]
-    fn country_filter_simple() {
-        let mut data = Servers::dummy_data();
+    fn describe(&self) -> String {
+        String::from("ModernProtocolFilter")
+    }
+}
 
-        data.filter(&CountryFilter::from("sg"));
+/// Filter that keeps servers with a load strictly below a provided value.
+///
+/// A server with a load equal to the threshold is removed. As loads are percentages, a threshold
+/// above 100 keeps every server. Use [LoadFilter::new](#method.new) to reject such values.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::Servers;
+/// use nordselect::filters::LoadFilter;
+/// let mut data = Servers::dummy_data();
+///
+/// // Filter on a load below 10%.
+/// data.filter(&LoadFilter::from(10));
+///
+/// assert!(data.perfect_server().is_some());
+/// ```
+pub struct LoadFilter {
+    /// The lowest load that is not allowed.
+    load: u8,
+}
+
+/// Ways to construct a LoadFilter.
+impl LoadFilter {
+    /// Creates a LoadFilter keeping the servers with a load below the given one. Returns `None` if
+    /// the load is higher than 100, as such a filter would never remove any server.
+    pub fn new(load: u8) -> Option<LoadFilter> {
+        if load > 100 {
+            None
+        } else {
+            Some(LoadFilter { load })
+        }
+    }
+}
+
+impl From<u8> for LoadFilter {
+    fn from(load: u8) -> LoadFilter {
+        LoadFilter { load }
+    }
+}
+
+impl Filter for LoadFilter {
+    fn filter(&self, server: &Server) -> bool {
+        server.load < self.load
+    }
+
+    fn describe(&self) -> String {
+        format!("LoadFilter(< {}%)", self.load)
+    }
+}
+
+/// Filter that keeps servers with a load within an inclusive range.
+///
+/// Servers with a very low load are often new or briefly unreachable, while servers with a high
+/// load are overloaded. This filter allows to avoid both.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::Servers;
+/// use nordselect::filters::LoadRangeFilter;
+/// let mut data = Servers::dummy_data();
+///
+/// // Filter on a load between 5% and 60%.
+/// data.filter(&LoadRangeFilter::new(5, 60).unwrap());
+///
+/// let load = data.perfect_server().unwrap().load;
+/// assert!(5 <= load && load <= 60);
+/// ```
+pub struct LoadRangeFilter {
+    /// The minimal allowed load.
+    min: u8,
+    /// The maximal allowed load.
+    max: u8,
+}
+
+/// Ways to construct a LoadRangeFilter.
+impl LoadRangeFilter {
+    /// Creates a LoadRangeFilter keeping servers with a load from `min` up to and including `max`.
+    /// Returns `None` if `min` is higher than `max`.
+    pub fn new(min: u8, max: u8) -> Option<LoadRangeFilter> {
+        if min > max {
+            None
+        } else {
+            Some(LoadRangeFilter { min, max })
+        }
+    }
+}
+
+impl Filter for LoadRangeFilter {
+    fn filter(&self, server: &Server) -> bool {
+        self.min <= server.load && server.load <= self.max
+    }
+
+    fn describe(&self) -> String {
+        format!("LoadRangeFilter({}%-{}%)", self.min, self.max)
+    }
+}
+
+/// Filter that keeps servers with a latency lower than or equal to a provided value, using the
+/// results of a ping test.
+///
+/// Servers that were not pinged are always removed.
+///
+/// # Example
+///
+/// ```no_run
+/// use nordselect::Servers;
+/// use nordselect::filters::PingFilter;
+/// use nordselect::sorters::PingSorter;
+/// let mut data = Servers::dummy_data();
+/// data.cut(10);
+///
+/// let pings = PingSorter::ping_single(&data, 2).unwrap();
+/// // Filter on 40 ms or less.
+/// data.filter(&PingFilter::new(&pings, 40));
+/// data.sort(&pings);
+/// ```
+pub struct PingFilter<'a> {
+    /// The results of the ping test.
+    pings: &'a PingSorter,
+    /// The maximal allowed latency, in milliseconds.
+    max_ms: usize,
+}
+
+/// Ways to construct a PingFilter.
+impl<'a> PingFilter<'a> {
+    /// Creates a PingFilter using the given ping results and maximal latency in milliseconds.
+    pub fn new(pings: &'a PingSorter, max_ms: usize) -> PingFilter<'a> {
+        PingFilter { pings, max_ms }
+    }
+}
+
+impl<'a> Filter for PingFilter<'a> {
+    fn filter(&self, server: &Server) -> bool {
+        match self.pings.latency(&server.domain) {
+            Some(latency) => latency <= self.max_ms.saturating_mul(1000),
+            None => false,
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("PingFilter(<= {} ms)", self.max_ms)
+    }
+}
+
+/// An ordered list of filters, which are applied one after another.
+///
+/// Unlike an [AndFilter](struct.AndFilter.html), every filter is applied separately, so it is
+/// possible to find out how many servers every filter removed using
+/// [apply_verbose](#method.apply_verbose).
+///
+/// # Example
+///
+/// ```
+/// use nordselect::{Servers, ServerCategory};
+/// use nordselect::filters::{CategoryFilter, CountryFilter, Filter, FilterSet};
+/// let mut data = Servers::dummy_data();
+///
+/// let filters: FilterSet = vec![
+///     Box::new(CountryFilter::from("BE")) as Box<dyn Filter>,
+///     Box::new(CategoryFilter::from(ServerCategory::Standard)),
+/// ]
+/// .into_iter()
+/// .collect();
+/// filters.apply(&mut data);
+///
+/// assert_eq!(data.perfect_server().unwrap().flag, "BE");
+/// ```
+pub struct FilterSet {
+    /// The filters, in order of application.
+    filters: Vec<Box<dyn Filter>>,
+}
+
+/// Ways to construct a `FilterSet`.
+impl FilterSet {
+    /// Builds a new, empty `FilterSet`.
+    pub fn new() -> FilterSet {
+        FilterSet {
+            filters: Vec::new(),
+        }
+    }
+}
+
+impl FromIterator<Box<dyn Filter>> for FilterSet {
+    fn from_iter<I: IntoIterator<Item = Box<dyn Filter>>>(iter: I) -> FilterSet {
+        FilterSet {
+            filters: Vec::from_iter(iter),
+        }
+    }
+}
+
+impl FilterSet {
+    /// Adds a new filter, which will be applied after the filters already added.
+    pub fn push(&mut self, filter: Box<dyn Filter>) {
+        self.filters.push(filter);
+    }
+
+    /// Returns the amount of filters in this set.
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Returns whether this set contains no filters.
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Applies all filters on the given servers, in order of insertion.
+    pub fn apply(&self, servers: &mut Servers) {
+        servers.filter_all(&self.filters);
+    }
+
+    /// Applies all filters on the given servers, in order of insertion. Returns the
+    /// [description](trait.Filter.html#method.describe) of every filter with the amount of
+    /// servers it removed.
+    pub fn apply_verbose(&self, servers: &mut Servers) -> Vec<(String, usize)> {
+        self.filters
+            .iter()
+            .map(|filter| (filter.describe(), servers.filter_verbose(filter.as_ref())))
+            .collect()
+    }
+}
+
+/// Filter that contains multiple Filter instances. Only servers fullfilling all requirements are
+/// kept, which allows to filter the servers in a single pass.
+///
+/// Logically, this should be viewed as an AND-gate, as every `Filter` should allow the server to
+/// be kept. The filters are evaluated in insertion order and evaluation stops at the first filter
+/// that rejects the server. An empty `AndFilter` keeps every server.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::{Servers, ServerCategory, Protocol};
+/// use nordselect::filters::{AndFilter, CategoryFilter, CountryFilter, ProtocolFilter};
+///
+/// let mut data = Servers::dummy_data();
+///
+/// let mut filter = AndFilter::new();
+/// filter.push(Box::new(CountryFilter::from("BE")));
+/// filter.push(Box::new(ProtocolFilter::from(Protocol::Tcp)));
+/// filter.push(Box::new(CategoryFilter::from(ServerCategory::Standard)));
+/// data.filter(&filter);
+///
+/// assert_eq!(data.perfect_server().unwrap().flag, "BE");
+/// ```
+pub struct AndFilter {
+    /// The actual filters
+    filters: Vec<Box<dyn Filter>>,
+}
+
+/// Ways to construct an `AndFilter`.
+impl AndFilter {
+    /// Builds a new, empty `AndFilter`.
+    pub fn new() -> AndFilter {
+        AndFilter {
+            filters: Vec::new(),
+        }
+    }
+
+    /// Builds a new, empty `AndFilter` with the given capacity.
+    pub fn with_capacity(capacity: usize) -> AndFilter {
+        AndFilter {
+            filters: Vec::with_capacity(capacity),
+        }
+    }
+}
+
+impl From<Vec<Box<dyn Filter>>> for AndFilter {
+    fn from(filters: Vec<Box<dyn Filter>>) -> AndFilter {
+        AndFilter { filters }
+    }
+}
+
+impl AndFilter {
+    /// Adds a new filter, which will be evaluated after the filters already added.
+    pub fn push(&mut self, filter: Box<dyn Filter>) {
+        self.filters.push(filter);
+    }
+}
+
+impl Filter for AndFilter {
+    fn filter(&self, server: &Server) -> bool {
+        self.filters.iter().all(|filter| filter.filter(server))
+    }
+
+    fn describe(&self) -> String {
+        format!("({})", describe_filters(&self.filters, " AND "))
+    }
+}
+
+/// Filter that contains multiple Filter instances. Servers fullfilling any of the requirements are
+/// kept.
+///
+/// Logically, this should be viewed as an OR-gate, as one `Filter` allowing the server is enough
+/// for it to be kept. Evaluation stops at the first filter that accepts the server. An empty
+/// `OrFilter` removes every server.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::{Servers, ServerCategory};
+/// use nordselect::filters::{CategoryFilter, OrFilter};
+///
+/// let mut data = Servers::dummy_data();
+///
+/// let mut filter = OrFilter::new();
+/// filter.push(Box::new(CategoryFilter::from(ServerCategory::P2P)));
+/// filter.push(Box::new(CategoryFilter::from(ServerCategory::Double)));
+/// data.filter(&filter);
+///
+/// let server = data.perfect_server().unwrap();
+/// assert!(server.categories.contains(&ServerCategory::P2P)
+///     || server.categories.contains(&ServerCategory::Double));
+/// ```
+pub struct OrFilter {
+    /// The actual filters
+    filters: Vec<Box<dyn Filter>>,
+}
+
+/// Ways to construct an `OrFilter`.
+impl OrFilter {
+    /// Builds a new, empty `OrFilter`.
+    pub fn new() -> OrFilter {
+        OrFilter {
+            filters: Vec::new(),
+        }
+    }
+
+    /// Builds a new, empty `OrFilter` with the given capacity.
+    pub fn with_capacity(capacity: usize) -> OrFilter {
+        OrFilter {
+            filters: Vec::with_capacity(capacity),
+        }
+    }
+}
+
+impl From<Vec<Box<dyn Filter>>> for OrFilter {
+    fn from(filters: Vec<Box<dyn Filter>>) -> OrFilter {
+        OrFilter { filters }
+    }
+}
+
+impl OrFilter {
+    /// Adds a new filter, which will be evaluated after the filters already added.
+    pub fn push(&mut self, filter: Box<dyn Filter>) {
+        self.filters.push(filter);
+    }
+}
+
+impl Filter for OrFilter {
+    fn filter(&self, server: &Server) -> bool {
+        self.filters.iter().any(|filter| filter.filter(server))
+    }
+
+    fn describe(&self) -> String {
+        format!("({})", describe_filters(&self.filters, " OR "))
+    }
+}
+
+/// Filter that contains multiple Filter instances. Only servers fullfilling all requirements are
+/// kept.
+///
+/// Logically, this should be viewed as an AND-gate, as every `Filter` should allow the server to
+/// be kept.
+#[deprecated(since = "1.4.0", note = "Use AndFilter instead.")]
+pub struct CombinedFilter {
+    // The actual filters
+    filters: Vec<Box<dyn Filter>>,
+}
+
+/// Ways to construct `CombinedFilters`.
+#[allow(deprecated)]
+impl CombinedFilter {
+    /// Builds a new `CombinedFilter`.
+    pub fn new() -> CombinedFilter {
+        CombinedFilter {
+            filters: Vec::new(),
+        }
+    }
+
+    /// Builds a new `CombinedFilter` with the given capacity.
+    pub fn with_capacity(capacity: usize) -> CombinedFilter {
+        CombinedFilter {
+            filters: Vec::with_capacity(capacity),
+        }
+    }
+}
+
+#[allow(deprecated)]
+impl From<Vec<Box<dyn Filter>>> for CombinedFilter {
+    fn from(filters: Vec<Box<dyn Filter>>) -> CombinedFilter {
+        CombinedFilter { filters }
+    }
+}
+
+#[allow(deprecated)]
+impl CombinedFilter {
+    /// Adds a new filter
+    pub fn add_filter(&mut self, filter: Box<dyn Filter>) {
+        self.filters.push(filter);
+    }
+}
+
+#[allow(deprecated)]
+impl Filter for CombinedFilter {
+    fn filter(&self, server: &Server) -> bool {
+        self.filters.iter().all(|filter| filter.filter(server))
+    }
+
+    fn describe(&self) -> String {
+        format!("({})", describe_filters(&self.filters, " AND "))
+    }
+}
+
+/// Filter the Servers using a given category.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::{Servers, ServerCategory};
+/// use nordselect::filters::CategoryFilter;
+/// let mut data = Servers::dummy_data();
+///
+/// // Filter on Standard servers.
+/// data.filter(&CategoryFilter::from(ServerCategory::Standard));
+///
+/// assert!(data.perfect_server().is_some());
+/// ```
+pub struct CategoryFilter {
+    category: ServerCategory,
+}
+
+impl From<ServerCategory> for CategoryFilter {
+    fn from(category: ServerCategory) -> CategoryFilter {
+        CategoryFilter { category }
+    }
+}
+
+impl Filter for CategoryFilter {
+    fn filter(&self, server: &Server) -> bool {
+        server.has_category(&self.category)
+    }
+
+    fn describe(&self) -> String {
+        format!("CategoryFilter({:?})", self.category)
+    }
+}
+
+/// Filter that keeps servers of which the domain matches a pattern.
+///
+/// The pattern can be a glob or a regular expression. Patterns are compiled when the filter is
+/// constructed, so invalid patterns return an error instead of failing while filtering.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::Servers;
+/// use nordselect::filters::DomainFilter;
+/// let mut data = Servers::dummy_data();
+///
+/// // All Belgian servers with a number starting with 1.
+/// data.filter(&DomainFilter::glob("be1*").unwrap());
+///
+/// assert!(data.perfect_server().unwrap().domain.starts_with("be1"));
+/// ```
+pub struct DomainFilter {
+    /// The compiled pattern.
+    pattern: Regex,
+    /// Whether the pattern should also be matched against the short name of the server.
+    match_name: bool,
+}
+
+/// Ways to construct a DomainFilter.
+impl DomainFilter {
+    /// Creates a DomainFilter from a glob pattern, where `*` matches any sequence of characters
+    /// and `?` matches any single character. Matching is case-insensitive.
+    ///
+    /// The glob should match either the full domain (`nl7*.nordvpn.com`) or the short name
+    /// (`nl7*`) entirely: `nl7` only matches `nl7.nordvpn.com`, not `nl70.nordvpn.com`.
+    pub fn glob(glob: &str) -> Result<DomainFilter, regex::Error> {
+        let mut pattern = String::from("(?i)^");
+        for c in glob.chars() {
+            match c {
+                '*' => pattern.push_str(".*"),
+                '?' => pattern.push('.'),
+                c => pattern.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        pattern.push('$');
+
+        Ok(DomainFilter {
+            pattern: Regex::new(&pattern)?,
+            match_name: true,
+        })
+    }
+
+    /// Creates a DomainFilter from a [regular expression](https://docs.rs/regex), which is
+    /// matched against the full domain. The expression is not anchored: use `^` and `$` to match
+    /// the domain as a whole.
+    pub fn regex(regex: &str) -> Result<DomainFilter, regex::Error> {
+        Ok(DomainFilter::from(Regex::new(regex)?))
+    }
+}
+
+impl From<Regex> for DomainFilter {
+    fn from(pattern: Regex) -> DomainFilter {
+        DomainFilter {
+            pattern,
+            match_name: false,
+        }
+    }
+}
+
+impl Filter for DomainFilter {
+    fn filter(&self, server: &Server) -> bool {
+        self.pattern.is_match(&server.domain)
+            || (self.match_name
+                && server
+                    .name()
+                    .map(|name| self.pattern.is_match(name))
+                    .unwrap_or(false))
+    }
+
+    fn describe(&self) -> String {
+        format!("DomainFilter({})", self.pattern.as_str())
+    }
+}
+
+/// Filter that keeps servers of which the [number](../servers/struct.Server.html#method.number)
+/// lies within an inclusive range. The range can be restricted to one country.
+///
+/// Servers without a number are removed.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::Servers;
+/// use nordselect::filters::NumberFilter;
+/// let mut data = Servers::dummy_data();
+///
+/// data.filter(&NumberFilter::new(1, 100).country("be"));
+///
+/// let server = data.perfect_server().unwrap();
+/// assert_eq!(server.flag, "BE");
+/// assert!(server.number().unwrap() <= 100);
+/// ```
+pub struct NumberFilter {
+    /// The lowest allowed number.
+    min: u32,
+    /// The highest allowed number.
+    max: u32,
+    /// The country to which the range applies, in uppercase.
+    country: Option<String>,
+}
+
+/// Ways to construct a NumberFilter.
+impl NumberFilter {
+    /// Creates a NumberFilter keeping servers with a number from `min` up to and including `max`.
+    ///
+    /// If `min` is greater than `max`, the bounds are swapped, so the range is never empty.
+    pub fn new(min: u32, max: u32) -> NumberFilter {
+        NumberFilter {
+            min: min.min(max),
+            max: min.max(max),
+            country: None,
+        }
+    }
+
+    /// Only keeps servers from the given country, noted according to
+    /// [ISO 3166-1 alpha-2](https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2).
+    pub fn country(mut self, country: &str) -> NumberFilter {
+        self.country = Some(country.to_ascii_uppercase());
+        self
+    }
+}
+
+impl Filter for NumberFilter {
+    fn filter(&self, server: &Server) -> bool {
+        if let Some(ref country) = self.country {
+            if *country != server.flag {
+                return false;
+            }
+        }
+        match server.number() {
+            Some(number) => self.min <= number && number <= self.max,
+            None => false,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self.country {
+            Some(ref country) => format!("NumberFilter({}, {}-{})", country, self.min, self.max),
+            None => format!("NumberFilter({}-{})", self.min, self.max),
+        }
+    }
+}
+
+/// Filter that removes the given servers.
+///
+/// Servers can be given by their full domain (`us2931.nordvpn.com`) or by their short name
+/// (`us2931`), case-insensitively. Entries that do not match any server are ignored.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::Servers;
+/// use nordselect::filters::BlocklistFilter;
+/// let mut data = Servers::dummy_data();
+///
+/// let best = data.perfect_server().unwrap();
+/// data.filter(&BlocklistFilter::from(vec![best.domain.as_str()]));
+///
+/// assert_ne!(data.perfect_server(), Some(best));
+/// ```
+pub struct BlocklistFilter {
+    /// The short names of the blocked servers, in lowercase.
+    blocked: HashSet<String>,
+}
+
+/// Ways to construct a BlocklistFilter.
+impl BlocklistFilter {
+    /// Reads the blocked servers from a file, containing one server per line. Empty lines and
+    /// lines starting with `#` are ignored.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<BlocklistFilter> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(BlocklistFilter::from(
+            contents
+                .lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .collect::<Vec<&str>>(),
+        ))
+    }
+
+    /// Converts a domain or short name into the short name, in lowercase.
+    fn normalize(server: &str) -> String {
+        let server = server.trim().to_lowercase();
+        match server.find(".nordvpn.com") {
+            Some(index) => server[..index].to_string(),
+            None => server,
+        }
+    }
+}
+
+impl<'a> From<Vec<&'a str>> for BlocklistFilter {
+    fn from(servers: Vec<&str>) -> BlocklistFilter {
+        BlocklistFilter {
+            blocked: HashSet::from_iter(
+                servers
+                    .into_iter()
+                    .map(|server| BlocklistFilter::normalize(server)),
+            ),
+        }
+    }
+}
+
+impl Filter for BlocklistFilter {
+    fn filter(&self, server: &Server) -> bool {
+        !self
+            .blocked
+            .contains(&BlocklistFilter::normalize(&server.domain))
+    }
+
+    fn describe(&self) -> String {
+        format!("BlocklistFilter({})", describe_set(&self.blocked))
+    }
+}
+
+/// Filter that removes servers with a dedicated IP, which require a separate subscription.
+///
+/// Servers in the `Dedicated` category are removed, even if they are in other categories (such as
+/// `Standard`) as well.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::{Servers, ServerCategory};
+/// use nordselect::filters::NonDedicatedFilter;
+/// let mut data = Servers::dummy_data();
+///
+/// data.filter(&NonDedicatedFilter);
+///
+/// assert!(!data.perfect_server().unwrap().categories.contains(&ServerCategory::Dedicated));
+/// ```
+pub struct NonDedicatedFilter;
+
+impl Filter for NonDedicatedFilter {
+    fn filter(&self, server: &Server) -> bool {
+        !server.has_category(&ServerCategory::Dedicated)
+    }
+
+    fn describe(&self) -> String {
+        String::from("NonDedicatedFilter")
+    }
+}
+
+/// Filter that keeps servers with a specific search keyword, such as `P2P`. Keywords are compared
+/// case-insensitively.
+///
+/// Servers without keywords are removed.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::Servers;
+/// use nordselect::filters::KeywordFilter;
+/// let mut data = Servers::dummy_data();
+///
+/// data.filter(&KeywordFilter::from("p2p"));
+///
+/// let server = data.perfect_server().unwrap();
+/// assert!(server.search_keywords.iter().any(|keyword| keyword.to_lowercase() == "p2p"));
+/// ```
+pub struct KeywordFilter {
+    /// The keyword, in lowercase.
+    keyword: String,
+}
+
+impl<'a> From<&'a str> for KeywordFilter {
+    fn from(keyword: &str) -> KeywordFilter {
+        KeywordFilter {
+            keyword: keyword.trim().to_lowercase(),
+        }
+    }
+}
+
+impl Filter for KeywordFilter {
+    fn filter(&self, server: &Server) -> bool {
+        server
+            .search_keywords
+            .iter()
+            .any(|keyword| keyword.to_lowercase() == self.keyword)
+    }
+
+    fn describe(&self) -> String {
+        format!("KeywordFilter({})", self.keyword)
+    }
+}
+
+/// Filter that keeps servers in a specific group, such as the region `Europe`. Groups are compared
+/// case-insensitively.
+///
+/// Only servers from the v1 API have groups. Other servers are removed.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::Servers;
+/// use nordselect::filters::GroupFilter;
+/// let mut data = Servers::from_api_v1().unwrap();
+///
+/// data.filter(&GroupFilter::from("europe"));
+///
+/// let server = data.perfect_server().unwrap();
+/// assert!(server.groups.iter().any(|group| group == "Europe"));
+/// ```
+pub struct GroupFilter {
+    /// The group, in lowercase.
+    group: String,
+}
+
+impl<'a> From<&'a str> for GroupFilter {
+    fn from(group: &str) -> GroupFilter {
+        GroupFilter {
+            group: group.trim().to_lowercase(),
+        }
+    }
+}
+
+impl Filter for GroupFilter {
+    fn filter(&self, server: &Server) -> bool {
+        server
+            .groups
+            .iter()
+            .any(|group| group.to_lowercase() == self.group)
+    }
+
+    fn describe(&self) -> String {
+        format!("GroupFilter({})", self.group)
+    }
+}
+
+/// Filter that keeps servers that are in all of the given categories.
+///
+/// An `AllCategoriesFilter` without categories keeps every server.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::{Servers, ServerCategory};
+/// use nordselect::filters::AllCategoriesFilter;
+/// let mut data = Servers::dummy_data();
+///
+/// // Filter on servers that are both Standard and P2P servers.
+/// data.filter(&AllCategoriesFilter::from(vec![ServerCategory::Standard, ServerCategory::P2P]));
+///
+/// let server = data.perfect_server().unwrap();
+/// assert!(server.categories.contains(&ServerCategory::Standard));
+/// assert!(server.categories.contains(&ServerCategory::P2P));
+/// ```
+pub struct AllCategoriesFilter {
+    /// The categories a server should be in.
+    categories: Vec<ServerCategory>,
+}
+
+impl From<Vec<ServerCategory>> for AllCategoriesFilter {
+    fn from(categories: Vec<ServerCategory>) -> AllCategoriesFilter {
+        AllCategoriesFilter { categories }
+    }
+}
+
+impl Filter for AllCategoriesFilter {
+    fn filter(&self, server: &Server) -> bool {
+        self.categories
+            .iter()
+            .all(|category| server.has_category(category))
+    }
+
+    fn describe(&self) -> String {
+        format!("AllCategoriesFilter({:?})", self.categories)
+    }
+}
+
+/// Filter that keeps servers that are in at least one of the given categories.
+///
+/// An `AnyCategoryFilter` without categories removes every server. Use
+/// [is_empty](#method.is_empty) to check for this.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::{Servers, ServerCategory};
+/// use nordselect::filters::AnyCategoryFilter;
+/// let mut data = Servers::dummy_data();
+///
+/// // Filter on servers that are Tor or Double VPN servers.
+/// data.filter(&AnyCategoryFilter::from(&[ServerCategory::Tor, ServerCategory::Double][..]));
+///
+/// let server = data.perfect_server().unwrap();
+/// assert!(server.categories.contains(&ServerCategory::Tor)
+///     || server.categories.contains(&ServerCategory::Double));
+/// ```
+pub struct AnyCategoryFilter {
+    /// The categories of which a server should be in at least one.
+    categories: Vec<ServerCategory>,
+}
+
+impl AnyCategoryFilter {
+    /// Returns whether no categories were given, in which case every server is removed.
+    pub fn is_empty(&self) -> bool {
+        self.categories.is_empty()
+    }
+}
+
+impl<'a> From<&'a [ServerCategory]> for AnyCategoryFilter {
+    fn from(categories: &[ServerCategory]) -> AnyCategoryFilter {
+        AnyCategoryFilter {
+            categories: categories.to_vec(),
+        }
+    }
+}
+
+impl Filter for AnyCategoryFilter {
+    fn filter(&self, server: &Server) -> bool {
+        self.categories
+            .iter()
+            .any(|category| server.has_category(category))
+    }
+
+    fn describe(&self) -> String {
+        format!("AnyCategoryFilter({:?})", self.categories)
+    }
+}
+
+/// Filter that negates the results of a given filter.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::Servers;
+/// use nordselect::filters::{CountryFilter, NegatingFilter};
+///
+/// let mut data = Servers::dummy_data();
+/// data.filter(&NegatingFilter::new(CountryFilter::from("BE")));
+///
+/// assert_ne!(data.perfect_server().unwrap().flag, "BE");
+/// ```
+pub struct NegatingFilter(Box<dyn Filter>);
+
+/// Ways to construct a NegatingFilter.
+impl NegatingFilter {
+    /// Creates a NegatingFilter that keeps exactly the servers the given filter removes.
+    pub fn new(filter: impl Filter + 'static) -> Self {
+        Self(Box::new(filter))
+    }
+}
+
+impl From<Box<dyn Filter + 'static>> for NegatingFilter {
+    fn from(filter: Box<dyn Filter + 'static>) -> Self {
+        Self(filter)
+    }
+}
+
+impl Filter for NegatingFilter {
+    fn filter(&self, server: &Server) -> bool {
+        !self.0.filter(server)
+    }
+
+    fn describe(&self) -> String {
+        format!("NOT {}", self.0.describe())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Servers;
+    use super::*;
+
+    /// Builds a synthetic server that supports no protocols, so tests do not depend on the
+    /// dummy data.
+    fn server(flag: &str, number: u32, load: u8, categories: Vec<ServerCategory>) -> Server {
+        categories
+            .into_iter()
+            .fold(Server::builder(), |builder, category| builder.category(category))
+            .flag(flag)
+            .domain(&format!("{}{}.nordvpn.com", flag.to_lowercase(), number))
+            .load(load)
+            .build()
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn country_filter_simple_legacy() {
+        let mut data = Servers::fixture();
+
+        data.filter(&CountryFilter::from_code("se".to_string()));
+
+        let server_opt = data.perfect_server();
+
+        assert!(server_opt.is_some());
+        assert_eq!(server_opt.unwrap().flag, "SE");
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn country_filter_advanced_legacy() {
+        let mut data = Servers::fixture();
+
+        data.filter(&CountryFilter::from_code("Se".to_string()));
+
+        let server_opt = data.perfect_server();
+
+        assert!(server_opt.is_some());
+        assert_eq!(server_opt.unwrap().flag, "SE");
+    }
+
+    #[test]
+    fn country_filter_simple() {
+        let mut data = Servers::fixture();
+
+        data.filter(&CountryFilter::from("se"));
 
         let server_opt = data.perfect_server();
 
         assert!(server_opt.is_some());
-        assert_eq!(server_opt.unwrap().flag, "SG");
+        assert_eq!(server_opt.unwrap().flag, "SE");
     }
 
     #[test]
     fn country_filter_advanced() {
-        let mut data = Servers::dummy_data();
+        let mut data = Servers::fixture();
 
-        data.filter(&CountryFilter::from("Sg"));
+        data.filter(&CountryFilter::from("Se"));
 
         let server_opt = data.perfect_server();
 
         assert!(server_opt.is_some());
-        assert_eq!(server_opt.unwrap().flag, "SG");
+        assert_eq!(server_opt.unwrap().flag, "SE");
     }
 
     #[test]
@@ -511,7 +1633,7 @@ mod tests {
 
     #[test]
     fn countries_filter_empty() {
-        let mut data = Servers::dummy_data();
+        let mut data = Servers::fixture();
 
         data.filter(&CountriesFilter::from(HashSet::with_capacity(0)));
 
@@ -522,8 +1644,8 @@ mod tests {
 
     #[test]
     fn countries_filter_simple() {
-        let mut data = Servers::dummy_data();
-        let vec = vec!["AE", "AL", "AR"];
+        let mut data = Servers::fixture();
+        let vec = vec!["AU", "BR", "JP"];
 
         data.filter(&CountriesFilter::from(HashSet::from_iter(
             vec.iter().map(|x| x.to_string()),
@@ -536,53 +1658,880 @@ mod tests {
     }
 
     #[test]
-    fn valid_regions() {
+    fn load_filter_boundaries() {
+        let mut data = Servers::from(vec![
+            server("BE", 1, 9, vec![ServerCategory::Standard]),
+            server("BE", 2, 10, vec![ServerCategory::Standard]),
+            server("BE", 3, 11, vec![ServerCategory::Standard]),
+        ]);
+
+        data.filter(&LoadFilter::from(10));
+
+        // A load equal to the threshold is removed.
+        assert_eq!(data.servers.len(), 1);
+        assert_eq!(data.servers[0].load, 9);
+    }
+
+    #[test]
+    fn load_filter_empty() {
+        let mut data = Servers::from(vec![
+            server("BE", 1, 70, vec![ServerCategory::Standard]),
+            server("NL", 1, 80, vec![ServerCategory::Standard]),
+        ]);
+
+        data.filter(&LoadFilter::from(50));
+
+        assert_eq!(data.perfect_server(), None);
+    }
+
+    #[test]
+    fn load_filter_new() {
+        assert!(LoadFilter::new(0).is_some());
+        assert!(LoadFilter::new(100).is_some());
+        assert!(LoadFilter::new(101).is_none());
+    }
+
+    #[test]
+    fn negating_filter_with_category() {
+        let mut data = Servers::from(vec![
+            server("BE", 1, 10, vec![ServerCategory::Standard]),
+            server("BE", 2, 10, vec![ServerCategory::Tor]),
+            server("NL", 1, 10, vec![ServerCategory::Standard]),
+            server("NL", 2, 10, vec![ServerCategory::Tor]),
+        ]);
+
+        data.filter(&NegatingFilter::new(CountryFilter::from("be")));
+        data.filter(&CategoryFilter::from(ServerCategory::Standard));
+
+        assert_eq!(data.servers.len(), 1);
+        assert_eq!(data.servers[0].domain, "nl1.nordvpn.com");
+    }
+
+    #[test]
+    fn negating_filter_boxed() {
+        let mut data = Servers::from(vec![
+            server("BE", 1, 10, vec![ServerCategory::Standard]),
+            server("BE", 2, 10, vec![ServerCategory::Tor]),
+        ]);
+
+        let boxed: Box<dyn Filter> = Box::new(CategoryFilter::from(ServerCategory::Tor));
+        data.filter(&NegatingFilter::from(boxed));
+
+        assert_eq!(data.servers.len(), 1);
+        assert_eq!(data.servers[0].domain, "be1.nordvpn.com");
+    }
+
+    /// A small list with servers in different countries, categories and loads.
+    fn mixed_servers() -> Servers {
+        Servers::from(vec![
+            server("BE", 1, 10, vec![ServerCategory::Standard]),
+            server("BE", 2, 60, vec![ServerCategory::P2P]),
+            server("NL", 1, 20, vec![ServerCategory::Standard, ServerCategory::P2P]),
+            server("NL", 2, 80, vec![ServerCategory::Double]),
+            server("DE", 1, 30, vec![ServerCategory::Tor]),
+            server("DE", 2, 40, vec![ServerCategory::Standard]),
+        ])
+    }
+
+    #[test]
+    fn and_filter_equals_sequential() {
+        let mut sequential = mixed_servers();
+        sequential.filter(&NegatingFilter::new(CountryFilter::from("DE")));
+        sequential.filter(&LoadFilter::from(60));
+        sequential.filter(&CategoryFilter::from(ServerCategory::P2P));
+
+        let mut combined = mixed_servers();
+        let mut filter = AndFilter::with_capacity(3);
+        filter.push(Box::new(NegatingFilter::new(CountryFilter::from("DE"))));
+        filter.push(Box::new(LoadFilter::from(60)));
+        filter.push(Box::new(CategoryFilter::from(ServerCategory::P2P)));
+        combined.filter(&filter);
+
+        assert_eq!(sequential.servers, combined.servers);
+        assert_eq!(combined.servers.len(), 1);
+    }
+
+    #[test]
+    fn and_filter_empty_keeps_all() {
+        let mut data = mixed_servers();
+        data.filter(&AndFilter::new());
+
+        assert_eq!(data.servers.len(), mixed_servers().servers.len());
+    }
+
+    #[test]
+    fn or_filter_union() {
+        let mut union = mixed_servers();
+        let mut filter = OrFilter::new();
+        filter.push(Box::new(CountryFilter::from("nl")));
+        filter.push(Box::new(CountryFilter::from("de")));
+        union.filter(&filter);
+
+        let mut expected = mixed_servers();
+        expected.filter(&NegatingFilter::new(CountryFilter::from("be")));
+
+        assert_eq!(union.servers, expected.servers);
+    }
+
+    #[test]
+    fn or_filter_empty_keeps_nothing() {
+        let mut data = mixed_servers();
+        data.filter(&OrFilter::new());
+
+        assert_eq!(data.perfect_server(), None);
+    }
+
+    #[test]
+    fn or_filter_in_and_filter() {
+        let mut data = mixed_servers();
+        let mut countries = OrFilter::new();
+        countries.push(Box::new(CountryFilter::from("nl")));
+        countries.push(Box::new(CountryFilter::from("de")));
+        let mut filter = AndFilter::new();
+        filter.push(Box::new(countries));
+        filter.push(Box::new(NegatingFilter::new(CategoryFilter::from(
+            ServerCategory::Tor,
+        ))));
+        data.filter(&filter);
+
+        assert_eq!(data.servers.len(), 3);
+        assert!(data.servers.iter().all(|server| server.flag != "BE"));
+    }
+
+    #[test]
+    fn region_countries_filter() {
+        let mut data = mixed_servers();
+        data.filter(&CountriesFilter::from(Region::EuropeanUnion));
+        assert!(data.servers.iter().any(|server| server.flag == "NL"));
+
+        let mut data = mixed_servers();
+        data.filter(&CountriesFilter::from(Region::Americas));
+        assert_eq!(data.perfect_server(), None);
+
+        let mut data = Servers::from(vec![
+            server("NL", 1, 10, vec![ServerCategory::Standard]),
+            server("US", 1, 10, vec![ServerCategory::Standard]),
+            server("JP", 1, 10, vec![ServerCategory::Standard]),
+            server("NZ", 1, 10, vec![ServerCategory::Standard]),
+        ]);
+        data.filter(&CountriesFilter::from(Region::Americas));
+        assert_eq!(data.servers.len(), 1);
+        assert_eq!(data.servers[0].flag, "US");
+    }
+
+    #[test]
+    fn continent_filter() {
+        let mut data = Servers::from(vec![
+            server("NL", 1, 10, vec![ServerCategory::Standard]),
+            server("US", 1, 10, vec![ServerCategory::Standard]),
+            server("XX", 1, 10, vec![ServerCategory::Standard]),
+        ]);
+        data.filter(&ContinentFilter::from(Continent::NorthAmerica));
+
+        assert_eq!(data.servers.len(), 1);
+        assert_eq!(data.servers[0].flag, "US");
+    }
+
+    #[test]
+    fn socks_servers_have_endpoint() {
+        let mut socks = server("NL", 2, 10, vec![ServerCategory::Standard]);
+        socks.features.socks = true;
+        let mut data = Servers::from(vec![
+            server("NL", 1, 10, vec![ServerCategory::Standard]),
+            socks,
+        ]);
+        assert_eq!(data.servers[0].socks_endpoint(), None);
+
+        data.filter(&ProtocolFilter::from(Protocol::Socks));
+
+        assert_eq!(data.servers.len(), 1);
+        for server in data.servers.iter() {
+            assert_eq!(
+                server.socks_endpoint(),
+                Some(("nl2.nordvpn.com".to_string(), 1080))
+            );
+        }
+    }
+
+    #[test]
+    fn protocol_filter_ikev2() {
+        let mut ikev2_only = server("NL", 2, 10, vec![ServerCategory::Standard]);
+        ikev2_only.features.ikev2 = true;
+        let mut openvpn_only = server("NL", 3, 10, vec![ServerCategory::Standard]);
+        openvpn_only.features.openvpn_udp = true;
+        openvpn_only.features.openvpn_tcp = true;
+        let mut data = Servers::from(vec![ikev2_only, openvpn_only]);
+
+        data.filter(&ProtocolFilter::from(Protocol::Ikev2));
+
+        assert_eq!(data.servers.len(), 1);
+        assert_eq!(data.servers[0].domain, "nl2.nordvpn.com");
+    }
+
+    #[test]
+    fn xor_protocol_independent_of_obfuscated_category() {
+        let mut xor = server("NL", 1, 10, vec![ServerCategory::Standard]);
+        xor.features.openvpn_xor_udp = true;
+        xor.features.openvpn_xor_tcp = true;
+        let mut obfuscated = server("NL", 2, 10, vec![ServerCategory::Obfuscated]);
+        obfuscated.features.openvpn_udp = true;
+        obfuscated.features.openvpn_tcp = true;
+        let data = Servers::from(vec![xor, obfuscated]);
+
+        for protocol in vec![Protocol::OpenVPNXUdp, Protocol::OpenVPNXTcp] {
+            let filter = ProtocolFilter::from(protocol);
+            assert!(filter.filter(&data.servers[0]));
+            assert!(!filter.filter(&data.servers[1]));
+        }
+
+        let filter = CategoryFilter::from(ServerCategory::Obfuscated);
+        assert!(!filter.filter(&data.servers[0]));
+        assert!(filter.filter(&data.servers[1]));
+    }
+
+    #[test]
+    fn proxy_protocols() {
+        let mut plain = server("NL", 1, 10, vec![ServerCategory::Standard]);
+        plain.features.proxy = true;
+        let mut separate = server("NL", 2, 10, vec![ServerCategory::Standard]);
+        separate.features.proxy_cybersec = true;
+        separate.features.proxy_ssl = true;
+        let mut combined = server("NL", 3, 10, vec![ServerCategory::Standard]);
+        combined.features.proxy_ssl_cybersec = true;
+        let servers = vec![plain, separate, combined];
+
+        let matching = |protocol: Protocol| -> Vec<&str> {
+            let filter = ProtocolFilter::from(protocol);
+            servers
+                .iter()
+                .filter(|server| filter.filter(server))
+                .map(|server| server.domain.as_str())
+                .collect()
+        };
+
+        assert_eq!(matching(Protocol::Proxy), vec!["nl1.nordvpn.com"]);
+        assert_eq!(matching(Protocol::CyberSecProxy), vec!["nl2.nordvpn.com"]);
+        assert_eq!(matching(Protocol::SslProxy), vec!["nl2.nordvpn.com"]);
+        assert_eq!(matching(Protocol::CyberSecSslProxy), vec!["nl3.nordvpn.com"]);
+    }
+
+    #[test]
+    fn all_categories_filter() {
+        let mut data = Servers::from(vec![
+            server("NL", 1, 10, vec![ServerCategory::P2P]),
+            server("NL", 2, 10, vec![ServerCategory::Standard, ServerCategory::P2P]),
+            server("NL", 3, 10, vec![ServerCategory::Tor]),
+        ]);
+
+        data.filter(&AllCategoriesFilter::from(vec![
+            ServerCategory::P2P,
+            ServerCategory::Standard,
+        ]));
+
+        assert_eq!(data.servers.len(), 1);
+        assert_eq!(data.servers[0].domain, "nl2.nordvpn.com");
+    }
+
+    #[test]
+    fn all_categories_filter_empty_keeps_all() {
+        let mut data = mixed_servers();
+        data.filter(&AllCategoriesFilter::from(Vec::new()));
+
+        assert_eq!(data.servers.len(), mixed_servers().servers.len());
+    }
+
+    #[test]
+    fn any_category_filter_is_union() {
+        let mut data = Servers::from(vec![
+            server("NL", 1, 10, vec![ServerCategory::Tor]),
+            server("NL", 2, 10, vec![ServerCategory::Double]),
+            server("NL", 3, 10, vec![ServerCategory::Standard]),
+        ]);
+
+        let filter = AnyCategoryFilter::from(&[ServerCategory::Tor, ServerCategory::Double][..]);
+        assert!(!filter.is_empty());
+        data.filter(&filter);
+
+        assert_eq!(data.servers.len(), 2);
+        assert_eq!(data.servers[0].domain, "nl1.nordvpn.com");
+        assert_eq!(data.servers[1].domain, "nl2.nordvpn.com");
+    }
+
+    #[test]
+    fn any_category_filter_empty_keeps_nothing() {
+        let mut data = mixed_servers();
+        let categories: &[ServerCategory] = &[];
+        let filter = AnyCategoryFilter::from(categories);
+        assert!(filter.is_empty());
+
+        data.filter(&filter);
+
+        assert_eq!(data.perfect_server(), None);
+    }
+
+    #[test]
+    fn domain_filter_glob_anchoring() {
+        let data = Servers::from(vec![
+            server("NL", 7, 10, vec![ServerCategory::Standard]),
+            server("NL", 70, 10, vec![ServerCategory::Standard]),
+            server("NL", 17, 10, vec![ServerCategory::Standard]),
+        ]);
+        let matching = |filter: DomainFilter| -> Vec<&str> {
+            data.servers
+                .iter()
+                .filter(|server| filter.filter(server))
+                .map(|server| server.domain.as_str())
+                .collect()
+        };
+
         assert_eq!(
-            Region::from_str("EU").unwrap().countries(),
-            vec![
-                "AT", "BE", "BG", "HR", "CY", "CZ", "DK", "EE", "FI", "FR", "DE", "GR", "HU", "IE",
-                "IT", "LV", "LT", "LU", "MT", "NL", "PL", "PT", "RO", "SK", "SI", "ES", "SE",
-            ]
+            matching(DomainFilter::glob("nl7").unwrap()),
+            vec!["nl7.nordvpn.com"]
+        );
+        assert_eq!(
+            matching(DomainFilter::glob("NL7*").unwrap()),
+            vec!["nl7.nordvpn.com", "nl70.nordvpn.com"]
+        );
+        assert_eq!(
+            matching(DomainFilter::glob("nl?7.nordvpn.com").unwrap()),
+            vec!["nl17.nordvpn.com"]
+        );
+    }
+
+    #[test]
+    fn domain_filter_regex() {
+        let data = Servers::from(vec![
+            server("DE", 750, 10, vec![ServerCategory::Standard]),
+            server("DE", 75, 10, vec![ServerCategory::Standard]),
+            server("NL", 750, 10, vec![ServerCategory::Standard]),
+        ]);
+        let filter = DomainFilter::regex(r"^de\d{3}\.nordvpn\.com$").unwrap();
+
+        assert!(filter.filter(&data.servers[0]));
+        assert!(!filter.filter(&data.servers[1]));
+        assert!(!filter.filter(&data.servers[2]));
+    }
+
+    #[test]
+    fn domain_filter_invalid() {
+        assert!(DomainFilter::regex("de(").is_err());
+        assert!(DomainFilter::glob("de(").is_ok());
+    }
+
+    #[test]
+    fn number_filter() {
+        let mut double = server("NL", 1, 10, vec![ServerCategory::Double]);
+        double.domain = "nl-uk1.nordvpn.com".to_string();
+        let data = Servers::from(vec![
+            server("US", 999, 10, vec![ServerCategory::Standard]),
+            server("US", 1000, 10, vec![ServerCategory::Standard]),
+            server("US", 2000, 10, vec![ServerCategory::Standard]),
+            server("US", 2001, 10, vec![ServerCategory::Standard]),
+            server("CA", 1500, 10, vec![ServerCategory::Standard]),
+            double,
+        ]);
+
+        assert_eq!(data.servers[1].number(), Some(1000));
+        assert_eq!(data.servers[5].number(), None);
+
+        let filter = NumberFilter::new(1000, 2000);
+        let kept: Vec<&str> = data
+            .servers
+            .iter()
+            .filter(|server| filter.filter(server))
+            .map(|server| server.domain.as_str())
+            .collect();
+        assert_eq!(
+            kept,
+            vec!["us1000.nordvpn.com", "us2000.nordvpn.com", "ca1500.nordvpn.com"]
+        );
+
+        let filter = NumberFilter::new(1000, 2000).country("us");
+        assert_eq!(
+            data.servers
+                .iter()
+                .filter(|server| filter.filter(server))
+                .count(),
+            2
+        );
+
+        // Inverted bounds are swapped.
+        let filter = NumberFilter::new(2000, 1000);
+        assert_eq!(filter.describe(), "NumberFilter(1000-2000)");
+        assert_eq!(
+            data.servers
+                .iter()
+                .filter(|server| filter.filter(server))
+                .count(),
+            3
         );
+    }
+
+    #[test]
+    fn exclude_countries_is_complement() {
+        let countries: HashSet<String> =
+            HashSet::from_iter(vec!["BE", "DE"].into_iter().map(String::from));
+
+        let mut included = mixed_servers();
+        included.filter(&CountriesFilter::from(countries.clone()));
+        let mut excluded = mixed_servers();
+        excluded.filter(&ExcludeCountriesFilter::from(countries));
+
+        assert_eq!(
+            included.servers.len() + excluded.servers.len(),
+            mixed_servers().servers.len()
+        );
+        assert!(excluded.servers.iter().all(|server| server.flag == "NL"));
+        assert!(included.servers.iter().all(|server| server.flag != "NL"));
+    }
+
+    #[test]
+    fn exclude_countries_case_insensitive() {
+        let mut data = mixed_servers();
+        data.filter(&ExcludeCountriesFilter::from(HashSet::from_iter(
+            vec!["be".to_string(), "De".to_string()],
+        )));
+
+        assert_eq!(data.servers.len(), 2);
+        assert!(data.servers.iter().all(|server| server.flag == "NL"));
+    }
+
+    #[test]
+    fn blocklist_filter() {
+        let mut data = mixed_servers();
+        data.filter(&BlocklistFilter::from(vec![
+            "be1",
+            "NL2.nordvpn.com",
+            "us2931",
+            "",
+        ]));
+
+        let domains: Vec<&str> = data
+            .servers
+            .iter()
+            .map(|server| server.domain.as_str())
+            .collect();
         assert_eq!(
-            Region::from_str("ЕЮ").unwrap().countries(),
+            domains,
             vec![
-                "AT", "BE", "BG", "HR", "CY", "CZ", "DK", "EE", "FI", "FR", "DE", "GR", "HU", "IE",
-                "IT", "LV", "LT", "LU", "MT", "NL", "PL", "PT", "RO", "SK", "SI", "ES", "SE",
+                "be2.nordvpn.com",
+                "nl1.nordvpn.com",
+                "de1.nordvpn.com",
+                "de2.nordvpn.com"
             ]
         );
+    }
+
+    #[test]
+    fn blocklist_filter_from_file() {
+        let path = std::env::temp_dir().join("nordselect_blocklist_test");
+        std::fs::write(&path, "# Broken IPv6\nde1.nordvpn.com\n\n  DE2  \n").unwrap();
+
+        let filter = BlocklistFilter::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut data = mixed_servers();
+        data.filter(&filter);
+        assert!(data.servers.iter().all(|server| server.flag != "DE"));
+        assert_eq!(data.servers.len(), 4);
+
+        assert!(BlocklistFilter::from_file("/nonexistent/blocklist").is_err());
+    }
+
+    #[test]
+    fn ping_filter() {
+        use std::collections::HashMap;
+
+        let mut data = mixed_servers();
+        let mut results = HashMap::new();
+        results.insert("be1.nordvpn.com".to_string(), 39_000);
+        results.insert("be2.nordvpn.com".to_string(), 40_000);
+        results.insert("nl1.nordvpn.com".to_string(), 40_001);
+        let pings = PingSorter::from(results);
+
+        data.filter(&PingFilter::new(&pings, 40));
+
+        // Servers that were not pinged are removed as well.
+        let domains: Vec<&str> = data
+            .servers
+            .iter()
+            .map(|server| server.domain.as_str())
+            .collect();
+        assert_eq!(domains, vec!["be1.nordvpn.com", "be2.nordvpn.com"]);
+
+        // A huge limit does not overflow.
+        assert!(PingFilter::new(&pings, usize::max_value()).filter(&data.servers[0]));
+    }
+
+    #[test]
+    fn load_range_filter_boundaries() {
+        let mut data = Servers::from(vec![
+            server("BE", 1, 4, vec![ServerCategory::Standard]),
+            server("BE", 2, 5, vec![ServerCategory::Standard]),
+            server("BE", 3, 60, vec![ServerCategory::Standard]),
+            server("BE", 4, 61, vec![ServerCategory::Standard]),
+        ]);
+
+        data.filter(&LoadRangeFilter::new(5, 60).unwrap());
+
+        let loads: Vec<u8> = data.servers.iter().map(|server| server.load).collect();
+        assert_eq!(loads, vec![5, 60]);
+    }
+
+    #[test]
+    fn load_range_filter_new() {
+        assert!(LoadRangeFilter::new(5, 5).is_some());
+        assert!(LoadRangeFilter::new(6, 5).is_none());
+    }
+
+    #[test]
+    fn load_range_filter_then_sort() {
+        use super::super::sorters::LoadSorter;
+
+        let mut data = mixed_servers();
+        data.filter(&LoadRangeFilter::new(15, 60).unwrap());
+        data.sort(&LoadSorter);
+
+        let loads: Vec<u8> = data.servers.iter().map(|server| server.load).collect();
+        assert_eq!(loads, vec![20, 30, 40, 60]);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn country_filter_normalizes_input() {
+        let filtered = |filter: CountryFilter| -> Vec<Server> {
+            let mut data = mixed_servers();
+            data.filter(&filter);
+            data.servers
+        };
+
+        let expected = filtered(CountryFilter::from("NL"));
+        assert_eq!(expected.len(), 2);
+        assert_eq!(filtered(CountryFilter::from("nl")), expected);
+        assert_eq!(filtered(CountryFilter::from(" nl ")), expected);
+        assert_eq!(filtered(CountryFilter::from_code(" nL\n".to_string())), expected);
+
+        assert!(filtered(CountryFilter::from("xx")).is_empty());
+    }
+
+    #[test]
+    fn countries_filter_from_str() {
+        let filter: CountriesFilter = "nl, DE,be,,".parse().unwrap();
+        assert_eq!(filter.countries.len(), 3);
+        assert!(filter.countries.contains("NL"));
+        assert!(filter.countries.contains("DE"));
+        assert!(filter.countries.contains("BE"));
+
+        let filter: CountriesFilter = "nl,NL,nl".parse().unwrap();
+        assert_eq!(filter.countries.len(), 1);
+
+        assert_eq!(
+            "nl,netherlands".parse::<CountriesFilter>().err(),
+            Some(ParseCountriesError {
+                token: "netherlands".to_string()
+            })
+        );
+        assert!("n1".parse::<CountriesFilter>().is_err());
+    }
+
+    #[test]
+    fn describe_filters() {
+        assert_eq!(CountryFilter::from("nl").describe(), "CountryFilter(NL)");
+        assert_eq!(
+            CategoryFilter::from(ServerCategory::P2P).describe(),
+            "CategoryFilter(P2P)"
+        );
+        assert_eq!(
+            ProtocolFilter::from(Protocol::Tcp).describe(),
+            "ProtocolFilter(Tcp)"
+        );
+        assert_eq!(
+            "nl,de".parse::<CountriesFilter>().unwrap().describe(),
+            "CountriesFilter(DE,NL)"
+        );
+
+        let mut filter = AndFilter::new();
+        filter.push(Box::new(CountryFilter::from("nl")));
+        filter.push(Box::new(NegatingFilter::new(LoadFilter::from(10))));
+        assert_eq!(
+            filter.describe(),
+            "(CountryFilter(NL) AND NOT LoadFilter(< 10%))"
+        );
+    }
+
+    #[test]
+    fn filter_verbose_counts() {
+        let mut data = mixed_servers();
+
+        assert_eq!(data.filter_verbose(&NegatingFilter::new(CountryFilter::from("be"))), 2);
+        assert_eq!(data.servers.len(), 4);
+        assert_eq!(data.filter_verbose(&LoadFilter::from(100)), 0);
+        assert_eq!(data.filter_verbose(&CountryFilter::from("xx")), 4);
+        assert!(data.servers.is_empty());
+    }
+
+    #[test]
+    fn parse_filters_from_str() {
+        let flags: HashSet<&str> = HashSet::from_iter(vec!["NL", "DE", "BE"]);
+        let describe = |token: &str| from_str(token, &flags).map(|filter| filter.describe());
+
+        assert_eq!(describe("p2p"), Ok("CategoryFilter(P2P)".to_string()));
+        assert_eq!(describe("P2P"), Ok("CategoryFilter(P2P)".to_string()));
+        assert_eq!(describe("Tor"), Ok("CategoryFilter(Tor)".to_string()));
+        assert_eq!(describe("TCP"), Ok("ProtocolFilter(Tcp)".to_string()));
+        assert_eq!(describe("nl"), Ok("CountryFilter(NL)".to_string()));
+        assert_eq!(describe("De"), Ok("CountryFilter(DE)".to_string()));
+
+        // Valid country codes without servers are unknown.
         assert_eq!(
-            Region::from_str("5E").unwrap().countries(),
-            vec!["AU", "CA", "NZ", "GB", "US"]
+            describe("us"),
+            Err(ParseFilterError {
+                token: "us".to_string()
+            })
         );
+        assert!(describe("netherlands").is_err());
+        assert!(describe("").is_err());
+    }
+
+    #[test]
+    fn modern_protocol_filter() {
+        let mut legacy = server("NL", 1, 10, vec![ServerCategory::Standard]);
+        legacy.features.pptp = true;
+        legacy.features.l2tp = true;
+        let mut udp = server("NL", 2, 10, vec![ServerCategory::Standard]);
+        udp.features.openvpn_udp = true;
+        udp.features.pptp = true;
+        let mut tcp = server("NL", 3, 10, vec![ServerCategory::Standard]);
+        tcp.features.openvpn_tcp = true;
+        let mut ikev2 = server("NL", 4, 10, vec![ServerCategory::Standard]);
+        ikev2.features.ikev2 = true;
+        ikev2.features.l2tp = true;
+        let mut data = Servers::from(vec![legacy, udp, tcp, ikev2]);
+
+        data.filter(&ModernProtocolFilter);
+
+        let domains: Vec<&str> = data
+            .servers
+            .iter()
+            .map(|server| server.domain.as_str())
+            .collect();
         assert_eq!(
-            Region::from_str("6E").unwrap().countries(),
-            vec!["AU", "CA", "FR", "NZ", "GB", "US"]
+            domains,
+            vec!["nl2.nordvpn.com", "nl3.nordvpn.com", "nl4.nordvpn.com"]
         );
+    }
+
+    #[test]
+    fn remove_dedicated_servers() {
+        let mut data = Servers::from(vec![
+            server("NL", 1, 10, vec![ServerCategory::Standard]),
+            server("NL", 2, 10, vec![ServerCategory::Dedicated]),
+            server(
+                "NL",
+                3,
+                10,
+                vec![ServerCategory::Standard, ServerCategory::Dedicated],
+            ),
+        ]);
+
+        data.remove_dedicated();
+
+        // Servers that are both Dedicated and Standard are removed as well.
+        assert_eq!(data.servers.len(), 1);
+        assert_eq!(data.servers[0].domain, "nl1.nordvpn.com");
+    }
+
+    #[test]
+    fn city_filter() {
+        let mut new_york = server("US", 1, 10, vec![ServerCategory::Standard]);
+        new_york.city = Some("New York".to_string());
+        let mut dallas = server("US", 2, 10, vec![ServerCategory::Standard]);
+        dallas.city = Some("Dallas".to_string());
+        let unknown = server("US", 3, 10, vec![ServerCategory::Standard]);
+        let mut data = Servers::from(vec![new_york, dallas, unknown]);
+
+        data.filter(&CityFilter::from(" new york"));
+
+        assert_eq!(data.servers.len(), 1);
+        assert_eq!(data.servers[0].domain, "us1.nordvpn.com");
+    }
+
+    #[test]
+    fn all_protocols_filter() {
+        let mut udp = server("NL", 1, 10, vec![ServerCategory::Standard]);
+        udp.features.openvpn_udp = true;
+        let mut tcp = server("NL", 2, 10, vec![ServerCategory::Standard]);
+        tcp.features.openvpn_tcp = true;
+        let mut dual = server("NL", 3, 10, vec![ServerCategory::Standard]);
+        dual.features.openvpn_udp = true;
+        dual.features.openvpn_tcp = true;
+        let mut data = Servers::from(vec![udp, tcp, dual]);
+
+        data.filter(&AllProtocolsFilter::from(vec![Protocol::Udp, Protocol::Tcp]));
+
+        assert_eq!(data.servers.len(), 1);
+        assert_eq!(data.servers[0].domain, "nl3.nordvpn.com");
+    }
+
+    #[test]
+    fn default_category_guard() {
+        let tor_only = || Servers::from(vec![
+            server("NL", 1, 5, vec![ServerCategory::Tor]),
+            server("NL", 2, 10, vec![ServerCategory::Standard]),
+        ]);
+
+        let mut guarded = tor_only();
+        guarded.default_category_guard();
+        assert_eq!(guarded.servers.len(), 1);
+        assert_eq!(guarded.servers[0].domain, "nl2.nordvpn.com");
+
+        let unguarded = tor_only();
+        assert_eq!(unguarded.servers.len(), 2);
+        assert_eq!(unguarded.perfect_server().unwrap().domain, "nl1.nordvpn.com");
+    }
+
+    #[test]
+    fn filter_set_order() {
+        let country = || Box::new(CountryFilter::from("nl")) as Box<dyn Filter>;
+        let load = || Box::new(LoadFilter::from(50)) as Box<dyn Filter>;
+
+        let country_first: FilterSet = vec![country(), load()].into_iter().collect();
+        let load_first: FilterSet = vec![load(), country()].into_iter().collect();
+        assert_eq!(country_first.len(), 2);
+
+        let mut a = mixed_servers();
+        let counts_a = country_first.apply_verbose(&mut a);
+        let mut b = mixed_servers();
+        let counts_b = load_first.apply_verbose(&mut b);
+
+        assert_eq!(a.servers, b.servers);
+        assert_eq!(a.servers.len(), 1);
         assert_eq!(
-            Region::from_str("9E").unwrap().countries(),
-            vec!["AU", "CA", "DK", "FR", "NL", "NO", "NZ", "GB", "US"]
+            counts_a,
+            vec![
+                ("CountryFilter(NL)".to_string(), 4),
+                ("LoadFilter(< 50%)".to_string(), 1)
+            ]
         );
         assert_eq!(
-            Region::from_str("14E").unwrap().countries(),
+            counts_b,
             vec![
-                "AU", "BE", "CA", "DE", "DK", "ES", "FR", "IT", "NL", "NO", "NZ", "GB", "SE", "US",
-            ],
+                ("LoadFilter(< 50%)".to_string(), 2),
+                ("CountryFilter(NL)".to_string(), 3)
+            ]
         );
 
-        // Make sure we do not forget a region
-        for (region, _) in Region::from_str_options().into_iter() {
-            assert!(Region::from_str(region).is_some());
+        let mut c = mixed_servers();
+        load_first.apply(&mut c);
+        assert_eq!(c.servers, a.servers);
+    }
+
+    #[test]
+    fn keyword_filter() {
+        let mut p2p = server("NL", 1, 10, vec![ServerCategory::Standard]);
+        p2p.search_keywords = vec!["P2P".to_string(), "Netflix".to_string()];
+        let mut data = Servers::from(vec![
+            p2p,
+            server("NL", 2, 10, vec![ServerCategory::Standard]),
+        ]);
+
+        data.filter(&KeywordFilter::from("netflix"));
+
+        assert_eq!(data.servers.len(), 1);
+        assert_eq!(data.servers[0].domain, "nl1.nordvpn.com");
+    }
+
+    #[test]
+    fn category_filter_unknown() {
+        let quantum = ServerCategory::UnknownServer("Quantum VPN servers".to_string());
+        let mut data = Servers::from(vec![
+            server("BE", 1, 10, vec![quantum.clone()]),
+            server(
+                "BE",
+                2,
+                10,
+                vec![ServerCategory::UnknownServer("Anti DDoS".to_string())],
+            ),
+            server("NL", 1, 10, vec![ServerCategory::Standard]),
+        ]);
+        data.filter(&CategoryFilter::from(quantum));
+        assert_eq!(data.servers.len(), 1);
+        assert_eq!(data.servers[0].domain, "be1.nordvpn.com");
+    }
+
+    #[test]
+    fn parse_wireguard_aliases() {
+        for token in &["wg_udp", "WireGuard", "NordLynx"] {
+            assert_eq!(protocol_from_str(token), Some(Protocol::WireGuardUdp));
+        }
+    }
+
+    #[test]
+    fn group_filter() {
+        let mut data = Servers::from(vec![
+            Server::builder()
+                .domain("nl1.nordvpn.com")
+                .category(ServerCategory::P2P)
+                .group("Europe")
+                .build(),
+            Server::builder()
+                .domain("us1.nordvpn.com")
+                .category(ServerCategory::P2P)
+                .group("The Americas")
+                .build(),
+            Server::builder()
+                .domain("nl2.nordvpn.com")
+                .category(ServerCategory::P2P)
+                .build(),
+        ]);
+        let filter = GroupFilter::from(" THE americas");
+        assert_eq!(filter.describe(), "GroupFilter(the americas)");
+        data.filter(&filter);
+        assert_eq!(data.servers.len(), 1);
+        assert_eq!(data.servers[0].domain, "us1.nordvpn.com");
+    }
+
+    #[test]
+    fn filter_all_equals_sequential() {
+        let filters: Vec<Box<dyn Filter>> = vec![
+            Box::new(CountryFilter::from("BE")),
+            Box::new(LoadFilter::from(50)),
+            Box::new(NegatingFilter::new(CategoryFilter::from(ServerCategory::Tor))),
+        ];
+
+        let mut sequential = mixed_servers();
+        for filter in &filters {
+            sequential.filter(filter.as_ref());
         }
+        let mut at_once = mixed_servers();
+        assert_eq!(at_once.filter_all(&filters), sequential.len());
+        assert_eq!(at_once.servers, sequential.servers);
+        assert!(!at_once.is_empty());
+
+        // Without filters, every server is kept.
+        let mut data = mixed_servers();
+        assert_eq!(data.filter_all(&[]), mixed_servers().len());
     }
 
     #[test]
-    fn invalid_regions() {
-        assert_eq!(Region::from_str("blablabla"), None);
-        assert_eq!(Region::from_str(""), None);
-        assert_eq!(Region::from_str("idk"), None);
-        assert_eq!(Region::from_str("test"), None);
-        assert_eq!(Region::from_str("12e"), None);
-        assert_eq!(Region::from_str("15e"), None);
+    fn region_filter() {
+        let dutch = server("NL", 1, 10, vec![ServerCategory::Standard]);
+        assert!(RegionFilter::from(Region::EuropeanUnion).filter(&dutch));
+        assert!(!RegionFilter::from(Region::Americas).filter(&dutch));
+        assert!(!RegionFilter::from(Region::Asia).filter(&dutch));
+        assert!(!RegionFilter::from(Region::Oceania).filter(&dutch));
+
+        let custom = RegionFilter::from(HashSet::from_iter(vec![
+            String::from("nl"),
+            String::from("BE"),
+        ]));
+        assert!(custom.filter(&dutch));
+        assert!(!custom.filter(&server("US", 1, 10, vec![ServerCategory::Standard])));
+
+        assert_eq!(
+            RegionFilter::from(Region::EuropeanUnion).describe(),
+            "RegionFilter(EU)"
+        );
+        assert_eq!(custom.describe(), "RegionFilter(BE,NL)");
     }
 }
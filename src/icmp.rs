@@ -0,0 +1,260 @@
+//! ICMP echo requests over unprivileged datagram sockets.
+//!
+//! On Linux, users in the groups of `net.ipv4.ping_group_range` can send ICMP echo requests using
+//! `IPPROTO_ICMP` datagram sockets, without the privileges oping needs. The kernel sets the
+//! identifier of the requests and only passes the replies to that identifier to the socket.
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use std::io;
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// The ICMP type of an echo request.
+const ECHO_REQUEST: u8 = 8;
+/// The ICMP type of an echo reply.
+const ECHO_REPLY: u8 = 0;
+/// The data sent along with every echo request.
+const PAYLOAD: &[u8] = b"nordselect";
+
+/// The sequence number of the next echo request.
+static SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns a new sequence number, to match replies to their request.
+pub fn next_sequence() -> u16 {
+    SEQUENCE.fetch_add(1, Ordering::Relaxed) as u16
+}
+
+/// Computes the internet checksum (RFC 1071) of the data.
+fn checksum(data: &[u8]) -> u16 {
+    let mut sum = data.chunks(2).fold(0u32, |sum, chunk| {
+        let word = (u32::from(chunk[0]) << 8) | u32::from(*chunk.get(1).unwrap_or(&0));
+        sum + word
+    });
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Builds an ICMP echo request with the given identifier, sequence number and payload.
+pub fn echo_request(identifier: u16, sequence: u16, payload: &[u8]) -> Vec<u8> {
+    let mut packet = vec![ECHO_REQUEST, 0, 0, 0];
+    packet.extend_from_slice(&[(identifier >> 8) as u8, identifier as u8]);
+    packet.extend_from_slice(&[(sequence >> 8) as u8, sequence as u8]);
+    packet.extend_from_slice(payload);
+    let checksum = checksum(&packet);
+    packet[2] = (checksum >> 8) as u8;
+    packet[3] = checksum as u8;
+    packet
+}
+
+/// Returns the identifier and sequence number of an ICMP echo reply, without IP header. Returns
+/// `None` if the packet is not a valid echo reply.
+pub fn parse_echo_reply(packet: &[u8]) -> Option<(u16, u16)> {
+    if packet.len() < 8 || packet[0] != ECHO_REPLY || packet[1] != 0 || checksum(packet) != 0 {
+        return None;
+    }
+    let identifier = (u16::from(packet[4]) << 8) | u16::from(packet[5]);
+    let sequence = (u16::from(packet[6]) << 8) | u16::from(packet[7]);
+    Some((identifier, sequence))
+}
+
+/// An unprivileged ICMP socket.
+#[cfg(target_os = "linux")]
+pub struct Socket(::libc::c_int);
+
+#[cfg(target_os = "linux")]
+impl Socket {
+    /// Opens a socket. Fails if the user is not allowed to use such sockets.
+    pub fn new() -> io::Result<Socket> {
+        use libc;
+
+        let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, libc::IPPROTO_ICMP) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Socket(fd))
+    }
+
+    /// Sends an echo request to the address and waits for its reply, returning the round trip
+    /// time. Returns `None` if no reply arrived within the timeout.
+    pub fn ping(
+        &self,
+        address: Ipv4Addr,
+        sequence: u16,
+        timeout: Duration,
+    ) -> io::Result<Option<Duration>> {
+        use libc;
+        use std::mem;
+        use std::time::Instant;
+
+        let packet = echo_request(0, sequence, PAYLOAD);
+        let target = libc::sockaddr_in {
+            sin_family: libc::AF_INET as libc::sa_family_t,
+            sin_port: 0,
+            sin_addr: libc::in_addr {
+                s_addr: u32::from(address).to_be(),
+            },
+            sin_zero: [0; 8],
+        };
+        let start = Instant::now();
+        let sent = unsafe {
+            libc::sendto(
+                self.0,
+                packet.as_ptr() as *const libc::c_void,
+                packet.len(),
+                0,
+                &target as *const libc::sockaddr_in as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+            )
+        };
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut buffer = [0u8; 1500];
+        loop {
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                return Ok(None);
+            }
+            self.set_receive_timeout(timeout - elapsed)?;
+            let received = unsafe {
+                libc::recv(
+                    self.0,
+                    buffer.as_mut_ptr() as *mut libc::c_void,
+                    buffer.len(),
+                    0,
+                )
+            };
+            if received < 0 {
+                let error = io::Error::last_os_error();
+                match error.kind() {
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => return Ok(None),
+                    io::ErrorKind::Interrupted => continue,
+                    _ => return Err(error),
+                }
+            }
+            // Replies to earlier requests that timed out are skipped.
+            match parse_echo_reply(&buffer[..received as usize]) {
+                Some((_, reply)) if reply == sequence => return Ok(Some(start.elapsed())),
+                _ => {}
+            }
+        }
+    }
+
+    /// Sets how long a receive waits for a packet.
+    fn set_receive_timeout(&self, timeout: Duration) -> io::Result<()> {
+        use libc;
+        use std::mem;
+
+        let value = libc::timeval {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            // A timeout of zero would wait forever.
+            tv_usec: timeout.subsec_micros().max(1) as libc::suseconds_t,
+        };
+        let result = unsafe {
+            libc::setsockopt(
+                self.0,
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &value as *const libc::timeval as *const libc::c_void,
+                mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Socket {
+    fn drop(&mut self) {
+        unsafe {
+            ::libc::close(self.0);
+        }
+    }
+}
+
+/// An unprivileged ICMP socket, which is only available on Linux.
+#[cfg(not(target_os = "linux"))]
+pub struct Socket;
+
+#[cfg(not(target_os = "linux"))]
+impl Socket {
+    /// Always fails, as this platform has no unprivileged ICMP sockets.
+    pub fn new() -> io::Result<Socket> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "unprivileged ICMP is only available on Linux",
+        ))
+    }
+
+    /// Never called, as no socket can be created.
+    pub fn ping(&self, _: Ipv4Addr, _: u16, _: Duration) -> io::Result<Option<Duration>> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksum_rfc_1071() {
+        // The example of RFC 1071, which sums to 0xddf2.
+        let data = [0x00, 0x01, 0xf2, 0x03, 0xf4, 0xf5, 0xf6, 0xf7];
+        assert_eq!(checksum(&data), !0xddf2);
+        // An odd length is padded with a zero.
+        assert_eq!(checksum(&[0x01]), !0x0100);
+    }
+
+    #[test]
+    fn build_echo_request() {
+        let packet = echo_request(0x1234, 0x0102, b"ab");
+        assert_eq!(packet.len(), 10);
+        assert_eq!(&packet[..2], &[8, 0]);
+        assert_eq!(&packet[4..], &[0x12, 0x34, 0x01, 0x02, b'a', b'b']);
+        // A packet including its checksum sums to zero.
+        assert_eq!(checksum(&packet), 0);
+    }
+
+    #[test]
+    fn parse_replies() {
+        let mut reply = echo_request(0x1234, 7, PAYLOAD);
+        // Turn the request into a reply, updating the checksum.
+        reply[0] = ECHO_REPLY;
+        reply[2] = 0;
+        reply[3] = 0;
+        let sum = checksum(&reply);
+        reply[2] = (sum >> 8) as u8;
+        reply[3] = sum as u8;
+        assert_eq!(parse_echo_reply(&reply), Some((0x1234, 7)));
+
+        // Requests, corrupted and truncated packets are no replies.
+        assert_eq!(parse_echo_reply(&echo_request(0x1234, 7, PAYLOAD)), None);
+        let mut corrupted = reply.clone();
+        corrupted[9] ^= 0xff;
+        assert_eq!(parse_echo_reply(&corrupted), None);
+        assert_eq!(parse_echo_reply(&reply[..6]), None);
+    }
+
+    #[test]
+    fn sequence_numbers_differ() {
+        assert_ne!(next_sequence(), next_sequence());
+    }
+
+    #[test]
+    #[ignore]
+    fn ping_localhost() {
+        // Needs a user in net.ipv4.ping_group_range: cargo test -- --ignored
+        let socket = Socket::new().unwrap();
+        let rtt = socket
+            .ping(Ipv4Addr::LOCALHOST, next_sequence(), Duration::from_secs(1))
+            .unwrap();
+        assert!(rtt.unwrap() < Duration::from_secs(1));
+    }
+}
@@ -26,7 +26,20 @@
 //!     assert!(servers.perfect_server().is_some());
 //! }
 //! ```
+//!
+//! # Testing
+//!
+//! The `test-util` feature adds `Servers::fixture`, a fixed list of servers covering every category
+//! and protocol, and `Server::builder` to create synthetic servers. Use these to test your code
+//! without downloading the servers from the NordVPN API:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! nordselect = { version = "*", features = ["test-util"] }
+//! ```
 
+/// Used to store snapshots of the server list.
+extern crate bincode;
 // Used to parse some data out of a string.
 extern crate regex;
 // Used to easily send GET requests.
@@ -34,17 +47,29 @@ extern crate reqwest;
 /// Used to parse JSON data from the API.
 #[macro_use]
 extern crate serde_derive;
+/// Used for the asynchronous API.
+#[cfg(feature = "async")]
+extern crate futures;
 /// Used for ping functionality.
 extern crate oping;
+/// Used for unprivileged pings.
+#[cfg(target_os = "linux")]
+extern crate libc;
 /// Used to parse JSON data from the API.
 extern crate serde;
 /// Used to parse JSON data from the API.
 extern crate serde_json;
 
+pub mod api;
+pub mod cache;
+pub mod continents;
 pub mod filters;
+mod icmp;
+pub mod regions;
 pub mod servers;
 pub mod sorters;
 
+pub use servers::Location;
 pub use servers::Protocol;
 pub use servers::Server;
 pub use servers::ServerCategory;
@@ -1,8 +1,9 @@
 extern crate clap;
 extern crate nordselect;
 
-use nordselect::filters::{self, Filter};
-use nordselect::{Protocol, ServerCategory, Servers};
+use nordselect::continents::Continent;
+use nordselect::filters::{self, Filter, FilterSet};
+use nordselect::{ServerCategory, Servers};
 use std::collections::HashSet;
 
 fn parse_cli_args<'a>() -> clap::ArgMatches<'a> {
@@ -50,6 +51,13 @@ fn parse_cli_args<'a>() -> clap::ArgMatches<'a> {
                 .help("Print the full domain instead of the short identifier (us1.nordvpn.com instead of us1)")
                 .takes_value(false),
         )
+        .arg(
+            Arg::with_name("continent")
+                .long("continent")
+                .value_name("CONTINENT")
+                .help("Only use servers located on CONTINENT (e.g. 'europe')")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("list_filters")
                 .long("filters")
@@ -72,9 +80,16 @@ fn parse_cli_args<'a>() -> clap::ArgMatches<'a> {
 
 fn show_available_filters(data: &Servers) {
     // Show protocols
-    println!("PROTOCOLS:\ttcp, udp, pptp, l2tp, tcp_xor, udp_xor, socks, cybersecproxy, sslproxy, cybersecsslproxy, proxy, wg_udp");
-    // Show server types
-    println!("SERVERS:\tstandard, dedicated, double, obfuscated, p2p, tor");
+    println!("PROTOCOLS:\ttcp, udp, pptp, l2tp, tcp_xor, udp_xor, socks, cybersecproxy, sslproxy, cybersecsslproxy, proxy, wg_udp, ikev2");
+    // Show server types that are in the list and can be used as a filter
+    let mut categories: Vec<String> = data
+        .categories()
+        .iter()
+        .map(|category| category.to_string())
+        .filter(|name| filters::category_from_str(name).is_some())
+        .collect();
+    categories.sort_unstable();
+    println!("SERVERS:\t{}", categories.join(", "));
 
     // Show countries
     let mut flags: Vec<String> = data.flags().iter().map(|&x| x.to_lowercase()).collect();
@@ -98,43 +113,21 @@ fn show_available_filters(data: &Servers) {
         iter.for_each(|flag| println!("{}\t{}", flag.0.to_lowercase(), flag.1));
         println!();
     }
+
+    // Show continents
+    println!("CONTINENTS (use --continent):\t{}", Continent::names().join(", "));
+    println!();
     println!("Any filter can be inverted using !");
 }
 
 fn parse_static_filter(filter: &str) -> Option<(Box<dyn Filter>, bool)> {
-    let mut is_category_filter = false;
-    let lib_filter = {
-        let mut category_filter = |category: ServerCategory| -> Box<dyn Filter> {
-            is_category_filter = true;
-            Box::new(filters::CategoryFilter::from(category))
-        };
-        let protocol_filter = |protocol: Protocol| -> Box<dyn Filter> {
-            Box::new(filters::ProtocolFilter::from(protocol))
-        };
-
-        match filter {
-            "p2p" => category_filter(ServerCategory::P2P),
-            "standard" => category_filter(ServerCategory::Standard),
-            "double" => category_filter(ServerCategory::Double),
-            "dedicated" => category_filter(ServerCategory::Dedicated),
-            "tor" => category_filter(ServerCategory::Tor),
-            "obfuscated" => category_filter(ServerCategory::Obfuscated),
-            "tcp" => protocol_filter(Protocol::Tcp),
-            "udp" => protocol_filter(Protocol::Udp),
-            "pptp" => protocol_filter(Protocol::Pptp),
-            "l2tp" => protocol_filter(Protocol::L2tp),
-            "tcp_xor" => protocol_filter(Protocol::OpenVPNXTcp),
-            "udp_xor" => protocol_filter(Protocol::OpenVPNXUdp),
-            "socks" => protocol_filter(Protocol::Socks),
-            "cybersecproxy" => protocol_filter(Protocol::CyberSecProxy),
-            "sslproxy" => protocol_filter(Protocol::SslProxy),
-            "cybersecsslproxy" => protocol_filter(Protocol::CyberSecSslProxy),
-            "proxy" => protocol_filter(Protocol::Proxy),
-            "wg_udp" => protocol_filter(Protocol::WireGuardUdp),
-            _ => return None,
-        }
-    };
-    Some((lib_filter, is_category_filter))
+    if let Some(category) = filters::category_from_str(filter) {
+        return Some((Box::new(filters::CategoryFilter::from(category)), true));
+    }
+    if let Some(protocol) = filters::protocol_from_str(filter) {
+        return Some((Box::new(filters::ProtocolFilter::from(protocol)), false));
+    }
+    None
 }
 
 fn consider_negating_filter<'a>(filter: &'a str) -> (&'a str, bool) {
@@ -151,11 +144,11 @@ fn consider_negating_filter_test() {
     assert_eq!(consider_negating_filter(""), ("", false));
 }
 
-fn parse_filters(cli_filters: clap::Values, data: &Servers) -> Vec<Box<dyn Filter>> {
+fn parse_filters(cli_filters: clap::Values, data: &Servers) -> FilterSet {
     // Parse which countries are in the data
     let flags = data.flags();
 
-    let mut lib_filters: Vec<Box<dyn Filter>> = Vec::new();
+    let mut lib_filters = FilterSet::new();
     let mut category_filter_added = false;
     let mut included_countries = HashSet::new();
     let mut excluded_countries = HashSet::new();
@@ -233,18 +226,16 @@ fn parse_filters(cli_filters: clap::Values, data: &Servers) -> Vec<Box<dyn Filte
     lib_filters
 }
 
-fn apply_filters(filters_to_apply: Vec<Box<dyn Filter>>, data: &mut Servers) {
-    for filter in filters_to_apply.iter() {
-        data.filter(filter.as_ref())
-    }
-}
-
 fn sort(data: &mut Servers, matches: &clap::ArgMatches) {
     let mut should_sort = true;
 
     // Perform ping test if required
     let s_ping = matches.is_present("single_ping");
     let m_ping = matches.is_present("multi_ping");
+    // There is nothing to sort if every server was filtered out.
+    if data.is_empty() {
+        return;
+    }
     if s_ping || m_ping {
         let tries_opt = matches.value_of("tries").unwrap().parse();
         if let Err(err) = tries_opt {
@@ -263,6 +254,8 @@ fn sort(data: &mut Servers, matches: &clap::ArgMatches) {
         let amount = amount_opt.unwrap();
         let tries = tries_opt.unwrap();
 
+        // Keep the least loaded servers, in the same order as `Servers::best`.
+        data.sort_best(None);
         data.cut(amount);
 
         match {
@@ -273,7 +266,15 @@ fn sort(data: &mut Servers, matches: &clap::ArgMatches) {
             }
         } {
             Ok(sorter) => {
-                data.sort(&sorter);
+                let summary = sorter.summary();
+                if summary.failed > 0 {
+                    eprintln!(
+                        "Could not ping {} of {} servers, these are ranked last",
+                        summary.failed,
+                        summary.failed + summary.succeeded
+                    );
+                }
+                data.sort_stable(&sorter);
                 should_sort = false;
             }
             Err(error) => {
@@ -309,7 +310,7 @@ fn sort(data: &mut Servers, matches: &clap::ArgMatches) {
     }
 
     if should_sort {
-        data.sort(&nordselect::sorters::LoadSorter);
+        data.sort_stable(&nordselect::sorters::LoadSorter);
     }
 }
 
@@ -333,15 +334,27 @@ fn main() {
     }
 
     // Detect filters
-    let filters_to_apply = parse_filters(
+    let mut filters_to_apply = parse_filters(
         matches
             .values_of("filter")
             .unwrap_or(clap::Values::default()),
         &data,
     );
 
+    if let Some(continent) = matches.value_of("continent") {
+        match continent.parse::<Continent>() {
+            Ok(continent) => {
+                filters_to_apply.push(Box::new(filters::ContinentFilter::from(continent)))
+            }
+            Err(err) => {
+                eprintln!("Error: {}. Use one of: {}", err, Continent::names().join(", "));
+                std::process::exit(1);
+            }
+        }
+    }
+
     // Filter servers that are not required.
-    apply_filters(filters_to_apply, &mut data);
+    filters_to_apply.apply(&mut data);
 
     // Sort the servers
     sort(&mut data, &matches);
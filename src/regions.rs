@@ -0,0 +1,182 @@
+//! Regions: groups of countries, such as the European Union or the Five Eyes.
+//!
+//! The countries of each region are listed in a table included in this crate, so they can be
+//! audited in one place.
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// A group of countries. Use `RegionFilter` or `CountriesFilter` to filter on a region.
+pub enum Region {
+    /// The [European Union](https://en.wikipedia.org/wiki/European_Union), consisting of 27 countries.
+    ///
+    /// Because of the Brexit, the United Kingdom is not included in this region
+    EuropeanUnion,
+    /// The European Economic Area, consisting of the European Union, Norway, Lichtenstein and Iceland.
+    EuropeanEconomicArea,
+    /// The Benelux consists of Belgium, The Netherlands and Luxembourgh
+    Benelux,
+    /// [5 eyes programme countries](https://en.wikipedia.org/wiki/Five_Eyes)
+    FiveEyes,
+    /// [6 eyes programme countries.](https://en.wikipedia.org/wiki/Five_Eyes#Other_international_cooperatives)
+    SixEyes,
+    /// [9 eyes programme countries.](https://en.wikipedia.org/wiki/Five_Eyes#Other_international_cooperatives)
+    NineEyes,
+    /// [14 eyes programme countries.](https://en.wikipedia.org/wiki/Five_Eyes#Other_international_cooperatives)
+    FourteenEyes,
+    /// Countries of North, Central and South America, including the Caribbean.
+    Americas,
+    /// Countries of Asia, including the Middle East.
+    Asia,
+    /// Countries of Oceania.
+    Oceania,
+}
+
+impl Region {
+    /// Tries to create a Region from a string slice. Returns a Region if there's one represented
+    /// by your str slice. Returns None otherwise.
+    ///
+    /// The provided str slice should be **uppercase**!
+    pub fn from_str(region_short: &str) -> Option<Region> {
+        match region_short {
+            "EU" | "ЕЮ" => Some(Region::EuropeanUnion),
+            "EEA" => Some(Region::EuropeanEconomicArea),
+            "BENELUX" => Some(Region::Benelux),
+            "5E" => Some(Region::FiveEyes),
+            "6E" => Some(Region::SixEyes),
+            "9E" => Some(Region::NineEyes),
+            "14E" => Some(Region::FourteenEyes),
+            "AMERICAS" => Some(Region::Americas),
+            "ASIA" => Some(Region::Asia),
+            "OCEANIA" => Some(Region::Oceania),
+            _ => None,
+        }
+    }
+
+    /// Returns all possible region codes with their respective meanings in human readable form.
+    /// Useful to provide lists to your users to choose from.
+    ///
+    /// Using a value from index 0 of the tuple will guaranteed give a Some when calling `[from_str](#method_from_str)`
+    pub fn from_str_options() -> [(&'static str, &'static str); 11] {
+        [
+            ("EU", "The European Union"),
+            ("ЕЮ", "The European Union (Cyrillic notation)"),
+            ("EEA", "The European Economic Area"),
+            ("BENELUX", "Countries of the Benelux"),
+            ("5E", "Countries involved in the Five Eyes programme."),
+            ("6E", "Countries involved in the Six Eyes programme."),
+            ("9E", "Countries involved in the Nine Eyes programme."),
+            ("14E", "Countries involved in the Fourteen Eyes programme."),
+            ("AMERICAS", "Countries of North, Central and South America."),
+            ("ASIA", "Countries of Asia, including the Middle East."),
+            ("OCEANIA", "Countries of Oceania."),
+        ]
+    }
+
+    /// Returns the main short notation for a given Region.
+    pub fn short(&self) -> &'static str {
+        match self {
+            Region::EuropeanUnion => "EU",
+            Region::EuropeanEconomicArea => "EEA",
+            Region::Benelux => "BENELUX",
+            Region::FiveEyes => "5E",
+            Region::SixEyes => "6E",
+            Region::NineEyes => "9E",
+            Region::FourteenEyes => "14E",
+            Region::Americas => "AMERICAS",
+            Region::Asia => "ASIA",
+            Region::Oceania => "OCEANIA",
+        }
+    }
+
+    /// Returns the countries in this Region, noted according to
+    /// [ISO 3166-1 alpha-2](https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2).
+    pub fn countries(&self) -> Vec<&str> {
+        match self {
+            Region::EuropeanEconomicArea => vec![
+                "AT", "BE", "BG", "HR", "CY", "CZ", "DK", "EE", "FI", "FR", "DE", "GR", "HU", "IE",
+                "IT", "LV", "LT", "LU", "MT", "NL", "PL", "PT", "RO", "SK", "SI", "ES", "SE", "NO",
+                "LI", "IS",
+            ],
+            Region::EuropeanUnion => vec![
+                "AT", "BE", "BG", "HR", "CY", "CZ", "DK", "EE", "FI", "FR", "DE", "GR", "HU", "IE",
+                "IT", "LV", "LT", "LU", "MT", "NL", "PL", "PT", "RO", "SK", "SI", "ES", "SE",
+            ],
+            Region::Benelux => vec!["BE", "LU", "NL"],
+            Region::FiveEyes => vec!["AU", "CA", "NZ", "GB", "US"],
+            Region::SixEyes => vec!["AU", "CA", "FR", "NZ", "GB", "US"],
+            Region::NineEyes => vec!["AU", "CA", "DK", "FR", "NL", "NO", "NZ", "GB", "US"],
+            Region::FourteenEyes => vec![
+                "AU", "BE", "CA", "DE", "DK", "ES", "FR", "IT", "NL", "NO", "NZ", "GB", "SE", "US",
+            ],
+            Region::Americas => vec![
+                "AG", "AR", "BB", "BO", "BR", "BS", "BZ", "CA", "CL", "CO", "CR", "CU", "DM", "DO",
+                "EC", "GD", "GT", "GY", "HN", "HT", "JM", "KN", "LC", "MX", "NI", "PA", "PE", "PR",
+                "PY", "SR", "SV", "TT", "US", "UY", "VC", "VE",
+            ],
+            Region::Asia => vec![
+                "AE", "AF", "AM", "AZ", "BD", "BH", "BN", "BT", "CN", "CY", "GE", "HK", "ID", "IL",
+                "IN", "IQ", "IR", "JO", "JP", "KG", "KH", "KP", "KR", "KW", "KZ", "LA", "LB", "LK",
+                "MM", "MN", "MO", "MV", "MY", "NP", "OM", "PH", "PK", "PS", "QA", "SA", "SG", "SY",
+                "TH", "TJ", "TL", "TM", "TR", "TW", "UZ", "VN", "YE",
+            ],
+            Region::Oceania => vec![
+                "AU", "FJ", "FM", "KI", "MH", "NR", "NZ", "PG", "PW", "SB", "TO", "TV", "VU", "WS",
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_regions() {
+        assert_eq!(
+            Region::from_str("EU").unwrap().countries(),
+            vec![
+                "AT", "BE", "BG", "HR", "CY", "CZ", "DK", "EE", "FI", "FR", "DE", "GR", "HU", "IE",
+                "IT", "LV", "LT", "LU", "MT", "NL", "PL", "PT", "RO", "SK", "SI", "ES", "SE",
+            ]
+        );
+        assert_eq!(
+            Region::from_str("ЕЮ").unwrap().countries(),
+            vec![
+                "AT", "BE", "BG", "HR", "CY", "CZ", "DK", "EE", "FI", "FR", "DE", "GR", "HU", "IE",
+                "IT", "LV", "LT", "LU", "MT", "NL", "PL", "PT", "RO", "SK", "SI", "ES", "SE",
+            ]
+        );
+        assert_eq!(
+            Region::from_str("5E").unwrap().countries(),
+            vec!["AU", "CA", "NZ", "GB", "US"]
+        );
+        assert_eq!(
+            Region::from_str("6E").unwrap().countries(),
+            vec!["AU", "CA", "FR", "NZ", "GB", "US"]
+        );
+        assert_eq!(
+            Region::from_str("9E").unwrap().countries(),
+            vec!["AU", "CA", "DK", "FR", "NL", "NO", "NZ", "GB", "US"]
+        );
+        assert_eq!(
+            Region::from_str("14E").unwrap().countries(),
+            vec![
+                "AU", "BE", "CA", "DE", "DK", "ES", "FR", "IT", "NL", "NO", "NZ", "GB", "SE", "US",
+            ],
+        );
+
+        // Make sure we do not forget a region
+        for (region, _) in Region::from_str_options().into_iter() {
+            assert!(Region::from_str(region).is_some());
+        }
+    }
+
+    #[test]
+    fn invalid_regions() {
+        assert_eq!(Region::from_str("blablabla"), None);
+        assert_eq!(Region::from_str(""), None);
+        assert_eq!(Region::from_str("idk"), None);
+        assert_eq!(Region::from_str("test"), None);
+        assert_eq!(Region::from_str("12e"), None);
+        assert_eq!(Region::from_str("15e"), None);
+    }
+}
@@ -1,21 +1,42 @@
 //! Data structures and methods to interact with the NordVPN servers.
-use filters::Filter;
+use api::{self, ApiConfig, ApiError, Retry};
+use bincode;
+use cache::Cache;
+use continents::Continent;
+use filters::{CategoryFilter, Filter, NonDedicatedFilter};
+#[cfg(feature = "async")]
+use futures::{Future, Stream};
 use reqwest;
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
 use serde_json;
-use sorters::Sorter;
+use sorters::{PingConfig, PingSorter, Score, ScoreWeights, Sorter};
 use std;
-use std::collections::HashSet;
-use std::hash::{Hash, Hasher};
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, Hash, Hasher};
 use std::iter::FromIterator;
+use std::net::IpAddr;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
 
-#[derive(Debug, Deserialize, PartialEq, Eq, Clone)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+#[serde(from = "String", into = "String")]
 /// The categories a Server can be in, as used by NordVPN.
+///
+/// Deserializes from the category names used by the API, such as `"Standard VPN servers"`.
 pub enum ServerCategory {
     /// A standard VPN server
     Standard,
     /// A VPN server with P2P services allowed.
     P2P,
     /// A VPN server with an obfuscated IP (i.e. floating IP).
+    ///
+    /// This category is not related to the xor obfuscation of OpenVPN traffic. Use
+    /// `Protocol::OpenVPNXUdp` or `Protocol::OpenVPNXTcp` to find servers supporting that.
     Obfuscated,
     /// A VPN server with a dedicated IP, which is used only by one VPN user at a time.
     Dedicated,
@@ -23,10 +44,13 @@ pub enum ServerCategory {
     Tor,
     /// A VPN server that can be used to connect to another NordVPN server.
     Double,
-    /// A VPN server that has a category that is not recognised by this library.\
+    /// A VPN server that has a category that is not recognised by this library. Contains the
+    /// name of the category, as used by the API.
+    ///
+    /// Unknown categories are only equal if their names are equal.
     ///
     /// Should you ever encouter this in the API response, feel free to open an issue.
-    UnknownServer,
+    UnknownServer(String),
 }
 
 impl From<String> for ServerCategory {
@@ -38,22 +62,58 @@ impl From<String> for ServerCategory {
             "Onion Over VPN" => ServerCategory::Tor,
             "Obfuscated Servers" => ServerCategory::Obfuscated,
             "Dedicated IP" => ServerCategory::Dedicated,
-            _ => ServerCategory::UnknownServer,
+            _ => ServerCategory::UnknownServer(input),
+        }
+    }
+}
+
+impl From<ServerCategory> for String {
+    /// Returns the name of the category, as used by the API.
+    fn from(category: ServerCategory) -> String {
+        match category {
+            ServerCategory::Standard => "Standard VPN servers".to_string(),
+            ServerCategory::P2P => "P2P".to_string(),
+            ServerCategory::Double => "Double VPN".to_string(),
+            ServerCategory::Tor => "Onion Over VPN".to_string(),
+            ServerCategory::Obfuscated => "Obfuscated Servers".to_string(),
+            ServerCategory::Dedicated => "Dedicated IP".to_string(),
+            ServerCategory::UnknownServer(name) => name,
         }
     }
 }
 
+/// Shows the short name of the category, as used by the CLI, such as `p2p`. Unknown categories
+/// show their name in the API.
+impl fmt::Display for ServerCategory {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            ServerCategory::Standard => "standard",
+            ServerCategory::P2P => "p2p",
+            ServerCategory::Double => "double",
+            ServerCategory::Tor => "tor",
+            ServerCategory::Obfuscated => "obfuscated",
+            ServerCategory::Dedicated => "dedicated",
+            ServerCategory::UnknownServer(ref name) => name,
+        };
+        formatter.write_str(name)
+    }
+}
+
 #[derive(Debug, Deserialize, PartialEq, Clone)]
 /// The struct used to identify categories, used in the API.
 ///
 /// **Should only be used when parsing API data.**
 struct ApiCategory {
-    /// The name of the category (converted into a type)
-    pub name: String,
+    /// The name of the category, converted into a type.
+    pub name: ServerCategory,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq, Clone)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+#[serde(default)]
 /// All protocols and other features a Server can have.
+///
+/// Features missing from the API response are disabled. Features unknown to this library are kept
+/// and can be looked up using [get](#method.get).
 pub struct Features {
     /// Support for IKEv2 protocol.
     pub ikev2: bool,
@@ -93,8 +153,78 @@ pub struct Features {
     pub proxy_ssl_cybersec: bool,
     /// Support for WireGuard over UDP
     pub wireguard_udp: bool,
+    /// Features that are not known by this library, by the name used in the API. Features without
+    /// a boolean value are left out.
+    #[serde(flatten, deserialize_with = "deserialize_extra_features")]
+    pub extra: HashMap<String, bool>,
+}
+
+impl Features {
+    /// Returns whether the feature with the given name, as used in the API, is supported. Returns
+    /// `None` if the feature is not known to this library and was not in the API response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::servers::Features;
+    ///
+    /// let mut features = Features::default();
+    /// features.extra.insert("teleport".to_string(), true);
+    ///
+    /// assert_eq!(features.get("ikev2"), Some(false));
+    /// assert_eq!(features.get("teleport"), Some(true));
+    /// assert_eq!(features.get("time_travel"), None);
+    /// ```
+    pub fn get(&self, name: &str) -> Option<bool> {
+        match name {
+            "ikev2" => Some(self.ikev2),
+            "openvpn_udp" => Some(self.openvpn_udp),
+            "openvpn_tcp" => Some(self.openvpn_tcp),
+            "socks" => Some(self.socks),
+            "proxy" => Some(self.proxy),
+            "pptp" => Some(self.pptp),
+            "l2tp" => Some(self.l2tp),
+            "openvpn_xor_udp" => Some(self.openvpn_xor_udp),
+            "openvpn_xor_tcp" => Some(self.openvpn_xor_tcp),
+            "proxy_cybersec" => Some(self.proxy_cybersec),
+            "proxy_ssl" => Some(self.proxy_ssl),
+            "proxy_ssl_cybersec" => Some(self.proxy_ssl_cybersec),
+            "wireguard_udp" => Some(self.wireguard_udp),
+            _ => self.extra.get(name).cloned(),
+        }
+    }
 }
 
+/// Deserializes the features unknown to this library, leaving out those without a boolean value.
+fn deserialize_extra_features<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, bool>, D::Error> {
+    let values: HashMap<String, serde_json::Value> = Deserialize::deserialize(deserializer)?;
+    Ok(values
+        .into_iter()
+        .filter_map(|(name, value)| value.as_bool().map(|enabled| (name, enabled)))
+        .collect())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+/// The geographical location of a server.
+pub struct Location {
+    /// The latitude, in degrees.
+    pub lat: f64,
+    /// The longitude, in degrees.
+    pub long: f64,
+}
+
+/// Locations are equal if their coordinates are exactly the same, which allows `Server` to
+/// implement `Eq`.
+impl PartialEq for Location {
+    fn eq(&self, other: &Location) -> bool {
+        self.lat.to_bits() == other.lat.to_bits() && self.long.to_bits() == other.long.to_bits()
+    }
+}
+
+impl Eq for Location {}
+
 #[derive(Debug, Deserialize)]
 /// The way servers are represented in the API response.
 struct ApiServer {
@@ -102,32 +232,379 @@ struct ApiServer {
     pub flag: String,
     /// The domain of this server.
     pub domain: String,
-    /// The current load on this server, written as a percentage (%)
-    pub load: u8,
+    /// The current load on this server, written as a percentage (%), if the API provided it.
+    #[serde(default)]
+    pub load: Option<u8>,
     /// Categories this server is in.
     pub categories: Vec<ApiCategory>,
     /// Features of the server
     pub features: Features,
+    /// The IP address of the server.
+    #[serde(default)]
+    pub ip_address: String,
+    /// The location of the server.
+    #[serde(default)]
+    pub location: Option<Location>,
+    /// The human-readable name of the server.
+    #[serde(default, rename = "name")]
+    pub pretty_name: String,
+    /// The name of the country this server is located in.
+    #[serde(default)]
+    pub country: String,
+    /// Keywords describing the server.
+    #[serde(default)]
+    pub search_keywords: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+/// A city, as represented in the v1 API response.
+///
+/// **Should only be used when parsing API data.**
+struct ApiV1City {
+    /// The name of the city.
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, Default)]
+/// A country, as represented in the v1 API response.
+///
+/// **Should only be used when parsing API data.**
+struct ApiV1Country {
+    /// The name of the country.
+    #[serde(default)]
+    pub name: String,
+    /// The country code, noted according to
+    /// [ISO 3166-1 alpha-2](https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2).
+    #[serde(default)]
+    pub code: String,
+    /// The city in this country.
+    #[serde(default)]
+    pub city: Option<ApiV1City>,
+}
+
+#[derive(Debug, Deserialize)]
+/// A country with servers, as represented in the countries endpoint of the v1 API.
+///
+/// **Should only be used when parsing API data.**
+struct ApiV1CountryId {
+    /// The identifier of the country, used to filter servers.
+    pub id: u32,
+    /// The country code, noted according to
+    /// [ISO 3166-1 alpha-2](https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2).
+    pub code: String,
+}
+
+/// Returns the identifier of the country with the given flag in a response of the countries
+/// endpoint of the v1 API, or `None` if NordVPN has no servers in that country.
+fn country_id(countries: &str, flag: &str) -> Result<Option<u32>, serde_json::Error> {
+    // The v1 API uses the official code of the United Kingdom.
+    let flag = if flag.eq_ignore_ascii_case("UK") {
+        "GB"
+    } else {
+        flag
+    };
+    let countries: Vec<ApiV1CountryId> = serde_json::from_str(countries)?;
+    Ok(countries
+        .into_iter()
+        .find(|country| country.code.eq_ignore_ascii_case(flag))
+        .map(|country| country.id))
+}
+
+/// Returns the URL of at most `limit` servers recommended by NordVPN, in the country with the
+/// given identifier of the v1 API if any.
+fn recommendations_url(limit: usize, country_id: Option<u32>) -> String {
+    match country_id {
+        Some(id) => format!(
+            "{}?limit={}&filters[country_id]={}",
+            API_RECOMMENDATIONS_URL, limit, id
+        ),
+        None => format!("{}?limit={}", API_RECOMMENDATIONS_URL, limit),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+/// A location, as represented in the v1 API response.
+///
+/// **Should only be used when parsing API data.**
+struct ApiV1Location {
+    /// The latitude, in degrees.
+    pub latitude: f64,
+    /// The longitude, in degrees.
+    pub longitude: f64,
+    /// The country of this location.
+    #[serde(default)]
+    pub country: ApiV1Country,
+}
+
+#[derive(Debug, Deserialize)]
+/// A technology (protocol) supported by a server, as represented in the v1 API response.
+///
+/// **Should only be used when parsing API data.**
+struct ApiV1Technology {
+    /// The identifier of the technology, such as `openvpn_udp`.
+    pub identifier: String,
+}
+
+#[derive(Debug, Deserialize, Default)]
+/// The type of a group, as represented in the v1 API response.
+///
+/// **Should only be used when parsing API data.**
+struct ApiV1GroupType {
+    /// The identifier of the type, such as `legacy_group_category` or `regions`.
+    #[serde(default)]
+    pub identifier: String,
+}
+
+#[derive(Debug, Deserialize)]
+/// A group a server is in, as represented in the v1 API response.
+///
+/// **Should only be used when parsing API data.**
+struct ApiV1Group {
+    /// The name of the group, such as `P2P` or `Europe`.
+    pub title: String,
+    /// The type of the group.
+    #[serde(default, rename = "type")]
+    pub group_type: ApiV1GroupType,
+}
+
+#[derive(Debug, Deserialize)]
+/// The way servers are represented in the v1 API response.
+struct ApiV1Server {
+    /// The human-readable name of the server.
+    #[serde(default)]
+    pub name: String,
+    /// The IP address of the server.
+    #[serde(default)]
+    pub station: String,
+    /// The domain of this server.
+    pub hostname: String,
+    /// The current load on this server, written as a percentage (%), if the API provided it.
+    #[serde(default)]
+    pub load: Option<u8>,
+    /// The locations of this server.
+    #[serde(default)]
+    pub locations: Vec<ApiV1Location>,
+    /// The technologies this server supports.
+    #[serde(default)]
+    pub technologies: Vec<ApiV1Technology>,
+    /// The groups this server is in.
+    #[serde(default)]
+    pub groups: Vec<ApiV1Group>,
+}
+
+impl<'a> From<&'a [ApiV1Technology]> for Features {
+    fn from(technologies: &[ApiV1Technology]) -> Features {
+        let mut features = Features::default();
+        for technology in technologies {
+            match technology.identifier.as_ref() {
+                "ikev2" => features.ikev2 = true,
+                "openvpn_udp" => features.openvpn_udp = true,
+                "openvpn_tcp" => features.openvpn_tcp = true,
+                "socks" => features.socks = true,
+                "proxy" => features.proxy = true,
+                "pptp" => features.pptp = true,
+                "l2tp" => features.l2tp = true,
+                "openvpn_xor_udp" => features.openvpn_xor_udp = true,
+                "openvpn_xor_tcp" => features.openvpn_xor_tcp = true,
+                "proxy_cybersec" => features.proxy_cybersec = true,
+                "proxy_ssl" => features.proxy_ssl = true,
+                "proxy_ssl_cybersec" => features.proxy_ssl_cybersec = true,
+                "wireguard_udp" => features.wireguard_udp = true,
+                // Technologies that are not known to this library are ignored.
+                _ => {}
+            }
+        }
+        features
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// A server by NordVPN.
+///
+/// Servers are equal if their domains are equal, even if their load or other fields differ: the
+/// same server in two downloads of the list is equal, so servers can be used in a `HashSet` to
+/// compare lists. Compare the fields to check whether anything changed.
 pub struct Server {
     /// The country this server is located in.
     pub flag: String,
     /// The domain of this server.
     pub domain: String,
-    /// The current load on this server.
+    /// The current load on this server. This is 0% if the load is unknown, see
+    /// [load_known](#structfield.load_known).
     pub load: u8,
+    /// Whether the API provided the load of this server. Servers with an unknown load are ordered
+    /// after the servers with a load of 0% by
+    /// [Servers::deprioritize_zero_load](struct.Servers.html#method.deprioritize_zero_load).
+    #[serde(default = "load_known")]
+    pub load_known: bool,
     /// Categories this server is in.
     pub categories: Vec<ServerCategory>,
+    /// Other groups this server is in, such as the regions `Europe` or `The Americas`.
+    ///
+    /// Only the v1 API provides groups, so this is empty for servers fetched using `from_api`.
+    pub groups: Vec<String>,
     /// Features of the server
+    #[serde(with = "features_fields")]
     pub features: Features,
+    /// The IP address of the server, as given by the API. Use [ip](#method.ip) to parse it.
+    ///
+    /// This is empty if the API did not provide an IP address.
+    pub ip_address: String,
+    /// The location of this server, if known.
+    pub location: Option<Location>,
+    /// The human-readable name of the server, such as `Netherlands #372`.
+    ///
+    /// This is empty if the API did not provide a name.
+    pub pretty_name: String,
+    /// The name of the country this server is located in, such as `Netherlands`.
+    ///
+    /// This is empty if the API did not provide a country.
+    pub country: String,
+    /// Keywords describing the server, such as `P2P`.
+    pub search_keywords: Vec<String>,
+    /// The city this server is located in, if known.
+    ///
+    /// The legacy API does not provide this information, so this is `None` for servers fetched
+    /// using `from_api`.
+    pub city: Option<String>,
+    /// The JSON object of this server in the response of the legacy API, including the fields
+    /// this library does not use, such as the id and the price.
+    ///
+    /// This is only kept when enabled using
+    /// [ApiConfig::keep_raw](../api/struct.ApiConfig.html#method.keep_raw), and is never stored in
+    /// snapshots.
+    #[serde(skip)]
+    pub raw: Option<serde_json::Value>,
+}
+
+/// The load of servers exported before `Server::load_known` existed was always known.
+fn load_known() -> bool {
+    true
+}
+
+/// (De)serializes Features as a tuple instead of a map with flattened extra features, so they can
+/// be stored in formats without field names, such as the snapshots written by
+/// `Servers::save_snapshot`. Human-readable formats, such as JSON, still use the map with the names
+/// of the API.
+mod features_fields {
+    use super::Features;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(features: &Features, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return features.serialize(serializer);
+        }
+        (
+            features.ikev2,
+            features.openvpn_udp,
+            features.openvpn_tcp,
+            features.socks,
+            features.proxy,
+            features.pptp,
+            features.l2tp,
+            features.openvpn_xor_udp,
+            features.openvpn_xor_tcp,
+            features.proxy_cybersec,
+            features.proxy_ssl,
+            features.proxy_ssl_cybersec,
+            features.wireguard_udp,
+            &features.extra,
+        )
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Features, D::Error> {
+        if deserializer.is_human_readable() {
+            return Features::deserialize(deserializer);
+        }
+        let (
+            ikev2,
+            openvpn_udp,
+            openvpn_tcp,
+            socks,
+            proxy,
+            pptp,
+            l2tp,
+            openvpn_xor_udp,
+            openvpn_xor_tcp,
+            proxy_cybersec,
+            proxy_ssl,
+            proxy_ssl_cybersec,
+            wireguard_udp,
+            extra,
+        ): (
+            bool,
+            bool,
+            bool,
+            bool,
+            bool,
+            bool,
+            bool,
+            bool,
+            bool,
+            bool,
+            bool,
+            bool,
+            bool,
+            HashMap<String, bool>,
+        ) = Deserialize::deserialize(deserializer)?;
+        Ok(Features {
+            ikev2,
+            openvpn_udp,
+            openvpn_tcp,
+            socks,
+            proxy,
+            pptp,
+            l2tp,
+            openvpn_xor_udp,
+            openvpn_xor_tcp,
+            proxy_cybersec,
+            proxy_ssl,
+            proxy_ssl_cybersec,
+            wireguard_udp,
+            extra,
+        })
+    }
 }
 
+/// Servers are compared by their domain only.
+impl PartialEq for Server {
+    fn eq(&self, other: &Server) -> bool {
+        self.domain == other.domain
+    }
+}
+
+impl Eq for Server {}
+
+/// Hashes only the domain, to be consistent with `PartialEq`.
 impl Hash for Server {
-    fn hash<H: Hasher>(&self, hasher: &mut H) {
-        self.domain.hash(hasher);
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.domain.hash(state);
+    }
+}
+
+/// Shows a one-line summary of the server: its short name (or domain), country, load and
+/// categories, such as `nl372 (NL, load 12%, standard/p2p)`.
+impl fmt::Display for Server {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{} ({}, load {}%",
+            self.name().unwrap_or(&self.domain),
+            self.flag,
+            self.load
+        )?;
+        if !self.categories.is_empty() {
+            let categories: Vec<String> = self
+                .categories
+                .iter()
+                .map(|category| category.to_string())
+                .collect();
+            write!(formatter, ", {}", categories.join("/"))?;
+        }
+        formatter.write_str(")")
     }
 }
 
@@ -136,90 +613,299 @@ impl From<ApiServer> for Server {
         Server {
             flag: api_server.flag,
             domain: api_server.domain,
-            load: api_server.load,
+            load: api_server.load.unwrap_or(0),
+            load_known: api_server.load.is_some(),
             categories: Vec::from_iter(
                 api_server
                     .categories
                     .into_iter()
-                    .map(|server_type| ServerCategory::from(server_type.name)),
+                    .map(|server_type| server_type.name),
             ),
+            groups: Vec::new(),
             features: api_server.features,
+            ip_address: api_server.ip_address,
+            location: api_server.location,
+            pretty_name: api_server.pretty_name,
+            country: api_server.country,
+            search_keywords: api_server.search_keywords,
+            city: None,
+            raw: None,
         }
     }
 }
 
-impl Server {
-    /// Returns the unique identifier of the server, without returning the full domain.
-    ///
-    /// This name is extracted from the `Server` everytime the function is called. Use it only to
-    /// create output.
-    pub fn name(&self) -> Option<&str> {
-        use regex::Regex;
-        let re = Regex::new(r"(.+)\.nordvpn.com").unwrap();
-        let caps = match re.captures(&self.domain) {
-            Some(caps) => caps,
-            None => {
-                return None;
+/// The list of servers in a response of the legacy API. Every server is converted while the list
+/// is deserialized, so the complete response is never kept in memory as `ApiServer`s.
+struct ApiServerList(Vec<Server>);
+
+impl<'de> Deserialize<'de> for ApiServerList {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ApiServerList, D::Error> {
+        deserializer
+            .deserialize_seq(ListVisitor { keep_raw: false })
+            .map(ApiServerList)
+    }
+}
+
+/// Like `ApiServerList`, but also keeps the JSON object of every server in `Server::raw`.
+struct RawApiServerList(Vec<Server>);
+
+impl<'de> Deserialize<'de> for RawApiServerList {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RawApiServerList, D::Error> {
+        deserializer
+            .deserialize_seq(ListVisitor { keep_raw: true })
+            .map(RawApiServerList)
+    }
+}
+
+/// Converts the elements of a list of `ApiServer`s one by one.
+struct ListVisitor {
+    /// Whether to keep the JSON object of every server.
+    keep_raw: bool,
+}
+
+impl<'de> Visitor<'de> for ListVisitor {
+    type Value = Vec<Server>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of servers")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<Server>, A::Error> {
+        let mut servers = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        if !self.keep_raw {
+            while let Some(api_server) = seq.next_element::<ApiServer>()? {
+                servers.push(Server::from(api_server));
             }
-        };
-        match caps.get(1) {
-            Some(matches) => Some(matches.as_str()),
-            None => None,
+            return Ok(servers);
         }
+        while let Some(raw) = seq.next_element::<serde_json::Value>()? {
+            let api_server = ApiServer::deserialize(&raw).map_err(de::Error::custom)?;
+            let mut server = Server::from(api_server);
+            server.raw = Some(raw);
+            servers.push(server);
+        }
+        Ok(servers)
     }
 }
 
-/// A list of individual servers.
-pub struct Servers {
-    /// The actual servers
-    pub servers: Vec<Server>,
-}
+/// The port NordVPN uses for its SOCKS5 proxies.
+pub const SOCKS_PORT: u16 = 1080;
 
-/// Functions to build and read data from the Servers.
-impl Servers {
-    /// Creates a Servers by reading the given text.
-    fn from_txt(txt: &str) -> Result<Servers, Box<dyn std::error::Error>> {
-        let api_servers: Vec<ApiServer> = serde_json::from_str(&txt)?;
+impl From<ApiV1Server> for Server {
+    fn from(api_server: ApiV1Server) -> Server {
+        let features = Features::from(api_server.technologies.as_slice());
+        let location = api_server.locations.into_iter().next();
+        let (flag, country, city, coordinates) = match location {
+            Some(location) => (
+                location.country.code.to_ascii_uppercase(),
+                location.country.name,
+                location.country.city.map(|city| city.name),
+                Some(Location {
+                    lat: location.latitude,
+                    long: location.longitude,
+                }),
+            ),
+            None => (String::new(), String::new(), None, None),
+        };
 
-        Ok(Servers {
-            servers: Vec::from_iter(
-                api_servers
+        // Other groups, such as regions, are not categories.
+        let (categories, groups): (Vec<ApiV1Group>, Vec<ApiV1Group>) = api_server
+            .groups
+            .into_iter()
+            .partition(|group| group.group_type.identifier == "legacy_group_category");
+
+        Server {
+            flag,
+            domain: api_server.hostname,
+            load: api_server.load.unwrap_or(0),
+            load_known: api_server.load.is_some(),
+            categories: Vec::from_iter(
+                categories
                     .into_iter()
-                    .map(|api_server| Server::from(api_server)),
+                    .map(|group| ServerCategory::from(group.title)),
             ),
-        })
+            groups: Vec::from_iter(groups.into_iter().map(|group| group.title)),
+            features,
+            ip_address: api_server.station,
+            location: coordinates,
+            pretty_name: api_server.name,
+            country,
+            search_keywords: Vec::new(),
+            city,
+            raw: None,
+        }
     }
+}
 
-    /// Downloads the list of servers from the API. Returns an error on failure.
-    ///
-    /// # Examples
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Debug, Clone)]
+/// Builds synthetic servers, to test code using this crate without network access. Created using
+/// [Server::builder](struct.Server.html#method.builder).
+///
+/// Only available with the `test-util` feature.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::{Server, ServerCategory};
+///
+/// let server = Server::builder()
+///     .flag("NL")
+///     .domain("nl1.nordvpn.com")
+///     .load(20)
+///     .category(ServerCategory::P2P)
+///     .build();
+/// assert_eq!(server.name(), Some("nl1"));
+/// ```
+pub struct ServerBuilder {
+    /// The server being built.
+    server: Server,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Default for ServerBuilder {
+    /// Starts with a server of which all fields are empty.
+    fn default() -> ServerBuilder {
+        ServerBuilder {
+            server: Server {
+                flag: String::new(),
+                domain: String::new(),
+                load: 0,
+                load_known: true,
+                categories: Vec::new(),
+                groups: Vec::new(),
+                features: Features::default(),
+                ip_address: String::new(),
+                location: None,
+                pretty_name: String::new(),
+                country: String::new(),
+                search_keywords: Vec::new(),
+                city: None,
+                raw: None,
+            },
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl ServerBuilder {
+    /// Sets the country of the server.
+    pub fn flag(mut self, flag: &str) -> ServerBuilder {
+        self.server.flag = flag.to_string();
+        self
+    }
+
+    /// Sets the domain of the server.
+    pub fn domain(mut self, domain: &str) -> ServerBuilder {
+        self.server.domain = domain.to_string();
+        self
+    }
+
+    /// Sets the load of the server.
+    pub fn load(mut self, load: u8) -> ServerBuilder {
+        self.server.load = load;
+        self.server.load_known = true;
+        self
+    }
+
+    /// Marks the load of the server as unknown, as if the API did not provide it.
+    pub fn unknown_load(mut self) -> ServerBuilder {
+        self.server.load = 0;
+        self.server.load_known = false;
+        self
+    }
+
+    /// Adds the server to the given category.
+    pub fn category(mut self, category: ServerCategory) -> ServerBuilder {
+        self.server.categories.push(category);
+        self
+    }
+
+    /// Adds the server to the given group, such as a region.
+    pub fn group(mut self, group: &str) -> ServerBuilder {
+        self.server.groups.push(group.to_string());
+        self
+    }
+
+    /// Sets the features of the server.
+    pub fn features(mut self, features: Features) -> ServerBuilder {
+        self.server.features = features;
+        self
+    }
+
+    /// Sets the IP address of the server.
+    pub fn ip_address(mut self, ip_address: &str) -> ServerBuilder {
+        self.server.ip_address = ip_address.to_string();
+        self
+    }
+
+    /// Sets the location of the server.
+    pub fn location(mut self, location: Location) -> ServerBuilder {
+        self.server.location = Some(location);
+        self
+    }
+
+    /// Sets the country name and the city of the server.
+    pub fn place(mut self, country: &str, city: &str) -> ServerBuilder {
+        self.server.country = country.to_string();
+        self.server.city = Some(city.to_string());
+        self
+    }
+
+    /// Returns the built server.
+    pub fn build(self) -> Server {
+        self.server
+    }
+}
+
+impl Server {
+    /// Returns a builder for a synthetic server. All fields are empty until they are set.
     ///
-    /// ```
-    /// let data = nordselect::Servers::from_api();
-    /// assert!(data.is_ok());
-    /// ```
-    pub fn from_api() -> Result<Servers, Box<dyn std::error::Error>> {
-        let mut data = reqwest::get("https://nordvpn.com/api/server")?;
-        let text = data.text()?;
+    /// Only available with the `test-util` feature.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn builder() -> ServerBuilder {
+        ServerBuilder::default()
+    }
 
-        Self::from_txt(&text)
+    /// Returns the unique identifier of the server, without returning the full domain.
+    ///
+    /// This name is extracted from the `Server` everytime the function is called. Use it only to
+    /// create output.
+    pub fn name(&self) -> Option<&str> {
+        match self.domain.rfind(".nordvpn.com") {
+            Some(end) if end > 0 => Some(&self.domain[..end]),
+            _ => None,
+        }
     }
 
-    /// Returns the data, fetched out of the `dummydata` file, generated using `dummydata.sh`.
+    /// Returns the number of the server, extracted from its short name (e.g. `1000` for
+    /// `us1000.nordvpn.com`).
     ///
-    /// Use this only for debugging, testing and benchmarking.
+    /// Returns `None` if the short name is not made of letters followed by digits, like the names
+    /// of Double VPN servers (`nl-uk1`).
     ///
     /// # Examples
+    ///
     /// ```
-    /// // If this fails, run `dummydata.sh` from the crate root.
-    /// nordselect::Servers::dummy_data();
+    /// use nordselect::Servers;
+    /// let data = Servers::dummy_data();
+    ///
+    /// let server = data.perfect_server().unwrap();
+    /// if let Some(number) = server.number() {
+    ///     assert!(server.domain.contains(&number.to_string()));
+    /// }
     /// ```
-    pub fn dummy_data() -> Servers {
-        let text = std::fs::read_to_string("dummydata").unwrap();
-        Self::from_txt(&text).unwrap()
+    pub fn number(&self) -> Option<u32> {
+        let name = self.name()?;
+        let digits = name.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+        if digits.len() == name.len() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
     }
 
-    /// Returns a set with all the flags (countries) in this set.
+    /// Returns the parsed IP address of the server. Returns `None` if the API did not provide a
+    /// valid IP address.
     ///
     /// # Examples
     ///
@@ -227,101 +913,3333 @@ impl Servers {
     /// use nordselect::Servers;
     /// let data = Servers::dummy_data();
     ///
-    /// assert!(data.flags().contains("BE"));
-    /// assert!(data.flags().contains("US"));
+    /// assert!(data.perfect_server().unwrap().ip().is_some());
+    /// ```
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.ip_address.parse().ok()
+    }
+
+    /// Returns whether the server belongs to the given category.
+    ///
+    /// # Examples
     ///
-    /// assert!(!data.flags().contains("XK")); // No servers in Kosovo
-    /// assert!(!data.flags().contains("EU")); // The EU is not a country
     /// ```
-    pub fn flags(&self) -> HashSet<&str> {
-        HashSet::from_iter(self.servers.iter().map(|server| server.flag.as_ref()))
+    /// use nordselect::{ServerCategory, Servers};
+    /// let data = Servers::dummy_data();
+    ///
+    /// assert!(data.iter().any(|server| server.has_category(&ServerCategory::P2P)));
+    /// ```
+    pub fn has_category(&self, category: &ServerCategory) -> bool {
+        self.categories.contains(category)
     }
 
-    /// Returns the best server, according to the given values. This should be called after all the
-    /// filters have been applied.
+    /// Returns whether the server supports the given protocol, according to its `features`.
     ///
-    /// Returns `None` if no `Server` fullfills all your needs.
+    /// The obfuscated protocols only match the xor features: `Protocol::OpenVPNXUdp` is not
+    /// supported by a server that only supports `openvpn_udp`.
     ///
     /// # Examples
     ///
     /// ```
-    /// use nordselect::{Servers, filters};
-    /// let mut data = Servers::dummy_data();
-    ///
-    /// data.filter(&filters::CountryFilter::from_code("".to_string()));
-    /// assert_eq!(data.perfect_server(), None);
+    /// use nordselect::{Protocol, Servers};
+    /// let data = Servers::dummy_data();
     ///
-    /// let mut data = Servers::dummy_data();
-    /// data.filter(&filters::CountryFilter::from_code("BE".to_string()));
-    /// assert!(data.perfect_server().is_some());
+    /// let server = data.perfect_server().unwrap();
+    /// assert_eq!(server.supports(Protocol::Tcp), server.features.openvpn_tcp);
     /// ```
-    pub fn perfect_server(&self) -> Option<Server> {
-        match self.servers.get(0) {
-            Some(x) => Some(x.clone()),
-            None => None,
+    pub fn supports(&self, protocol: Protocol) -> bool {
+        match protocol {
+            Protocol::Tcp => self.features.openvpn_tcp,
+            Protocol::Udp => self.features.openvpn_udp,
+            Protocol::Pptp => self.features.pptp,
+            Protocol::L2tp => self.features.l2tp,
+            Protocol::OpenVPNXTcp => self.features.openvpn_xor_tcp,
+            Protocol::OpenVPNXUdp => self.features.openvpn_xor_udp,
+            Protocol::Socks => self.features.socks,
+            Protocol::CyberSecProxy => self.features.proxy_cybersec,
+            Protocol::SslProxy => self.features.proxy_ssl,
+            Protocol::CyberSecSslProxy => self.features.proxy_ssl_cybersec,
+            Protocol::Proxy => self.features.proxy,
+            Protocol::WireGuardUdp => self.features.wireguard_udp,
+            Protocol::Ikev2 => self.features.ikev2,
         }
     }
-}
 
-#[derive(PartialEq)]
-/// A protocol to connect to the VPN server.
-pub enum Protocol {
-    /// OpenVPN over the [User Datagram Protocol](https://en.wikipedia.org/wiki/User_Datagram_Protocol)
-    Udp,
-    /// OpenVPN over the [Transmission Control Protocol](https://en.wikipedia.org/wiki/Transmission_Control_Protocol)
-    Tcp,
-    /// The older Point-to-Point Tunneling Protocol
+    /// Returns the host and port of the SOCKS5 proxy of this server. Returns `None` if the server
+    /// does not support SOCKS.
     ///
-    /// **Warning**: this protocol is considered unsafe. Usage is discouraged.
+    /// Use a `ProtocolFilter` with `Protocol::Socks` to keep only servers that support it.
     ///
-    /// From the NordVPN site:
-    /// > Although technically you can use the L2TP/PPTP protocol, it has serious security flaws.
-    /// > Whenever possible, we recommend choosing OpenVPN or IKEv2/IPSec instead.
-    Pptp,
-    /// The Layer 2 Tunneling Protocol
+    /// # Examples
     ///
-    /// **Warning**: this protocol is considered unsafe. Usage is discouraged.
+    /// ```
+    /// use nordselect::{Protocol, Servers};
+    /// use nordselect::filters::ProtocolFilter;
+    /// let mut data = Servers::dummy_data();
     ///
-    /// From the NordVPN site:
-    /// > Although technically you can use the L2TP/PPTP protocol, it has serious security flaws.
-    /// > Whenever possible, we recommend choosing OpenVPN or IKEv2/IPSec instead.
-    L2tp,
-    /// OpenVPN over TCP with xor obfuscation
-    OpenVPNXTcp,
-    /// OpenVPN over UDP with xor obfuscation
-    OpenVPNXUdp,
-    /// Support for the SOCKS protocol.
-    Socks,
-    /// Support for a proxy with CyberSec
-    CyberSecProxy,
-    /// Support for a proxy with SSL
-    SslProxy,
-    /// Support for a proxy with CyberSec and SSL
-    CyberSecSslProxy,
-    /// Use the server as a proxy
-    Proxy,
-    /// WireGuard over UDP
-    WireGuardUdp,
+    /// data.filter(&ProtocolFilter::from(Protocol::Socks));
+    ///
+    /// let (host, port) = data.perfect_server().unwrap().socks_endpoint().unwrap();
+    /// assert!(host.ends_with(".nordvpn.com"));
+    /// assert_eq!(port, 1080);
+    /// ```
+    pub fn socks_endpoint(&self) -> Option<(String, u16)> {
+        if self.supports(Protocol::Socks) {
+            Some((self.domain.clone(), SOCKS_PORT))
+        } else {
+            None
+        }
+    }
 }
 
-/// All manipulations that will alter the servers.
+/// The URL of the legacy API, used by [Servers::from_api](struct.Servers.html#method.from_api).
+pub const API_URL: &str = "https://nordvpn.com/api/server";
+/// The URL of the v1 API, used by
+/// [Servers::from_api_v1](struct.Servers.html#method.from_api_v1).
+pub const API_V1_URL: &str = "https://api.nordvpn.com/v1/servers?limit=0";
+/// The URL of the countries with servers in the v1 API, used by
+/// [Servers::from_api_for_country](struct.Servers.html#method.from_api_for_country).
+pub const API_COUNTRIES_URL: &str = "https://api.nordvpn.com/v1/servers/countries";
+/// The URL of the recommendations in the v1 API, used by
+/// [Servers::from_recommendations](struct.Servers.html#method.from_recommendations).
+pub const API_RECOMMENDATIONS_URL: &str = "https://api.nordvpn.com/v1/servers/recommendations";
+
+/// The version of the format written by `Servers::save_snapshot`. Change this whenever the
+/// serialized form of `Server` changes.
+const SNAPSHOT_VERSION: u8 = 4;
+
+/// The header of the CSV written by `Servers::to_csv`.
+const CSV_COLUMNS: [&str; 10] = [
+    "domain",
+    "flag",
+    "load",
+    "categories",
+    "openvpn_udp",
+    "openvpn_tcp",
+    "ikev2",
+    "wireguard_udp",
+    "openvpn_xor_udp",
+    "openvpn_xor_tcp",
+];
+
+/// Quotes a CSV cell if it contains a comma, quote or line break.
+fn csv_cell(value: &str) -> String {
+    if value.contains(|c: char| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Statistics about the load of a group of servers, returned by
+/// [Servers::load_stats_by_country](struct.Servers.html#method.load_stats_by_country).
+pub struct LoadStats {
+    /// The number of servers.
+    pub count: usize,
+    /// The lowest load, in percent.
+    pub min: u8,
+    /// The highest load, in percent.
+    pub max: u8,
+    /// The average load, in percent.
+    pub mean: f64,
+}
+
+/// Returns the short name of the server (`nl1` for `nl1.nordvpn.com`): the first label of its
+/// domain. Unlike [Server::name](struct.Server.html#method.name), this also works for domains
+/// outside `nordvpn.com`.
+fn short_name(server: &Server) -> &str {
+    server.domain.split('.').next().unwrap_or("")
+}
+
+/// Returns whether the server has the given domain or short name, which should be trimmed. Case
+/// is ignored. Only servers of `nordvpn.com` have a short name, see
+/// [Server::name](struct.Server.html#method.name).
+fn has_name(server: &Server, name: &str) -> bool {
+    server.domain.eq_ignore_ascii_case(name)
+        || server
+            .name()
+            .map_or(false, |short| short.eq_ignore_ascii_case(name))
+}
+
+/// Orders servers on their short name.
+fn cmp_short_name(a: &Server, b: &Server) -> std::cmp::Ordering {
+    short_name(a).cmp(short_name(b))
+}
+
+/// Orders servers using the Sorter, then on their short name. See
+/// [Servers::sort_stable](struct.Servers.html#method.sort_stable).
+fn cmp_stable(a: &Server, b: &Server, sorter: &dyn Sorter) -> std::cmp::Ordering {
+    sorter.sort(a, b).then_with(|| cmp_short_name(a, b))
+}
+
+/// Orders servers on their load, then on their latency if they were pinged, then on their short
+/// name. See [Servers::best](struct.Servers.html#method.best).
+fn cmp_best(a: &Server, b: &Server, pings: Option<&PingSorter>) -> std::cmp::Ordering {
+    a.load
+        .cmp(&b.load)
+        .then_with(|| pings.map_or(std::cmp::Ordering::Equal, |pings| pings.sort(a, b)))
+        .then_with(|| cmp_short_name(a, b))
+}
+
+/// Returns a random seed, different for every call.
+fn random_seed() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// The SplitMix64 random number generator, which is fast and good enough to spread users over
+/// servers. It is not suitable for cryptography.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    /// Returns the next random number.
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a random number lower than `max`, which should not be 0.
+    fn below(&mut self, max: usize) -> usize {
+        (self.next_u64() % max as u64) as usize
+    }
+}
+
+#[derive(Clone)]
+/// A list of individual servers.
+pub struct Servers {
+    /// The actual servers
+    pub servers: Vec<Server>,
+    /// The moment the servers were downloaded from the API. Their load changes quickly, so use
+    /// [age](#method.age) or [is_stale](#method.is_stale) to check whether they are still usable.
+    pub fetched_at: SystemTime,
+}
+
+/// Creates a Servers from the given list, fetched now.
+impl From<Vec<Server>> for Servers {
+    fn from(servers: Vec<Server>) -> Servers {
+        Servers {
+            servers,
+            fetched_at: SystemTime::now(),
+        }
+    }
+}
+
+/// Collects servers into a Servers, fetched now.
+///
+/// # Examples
+///
+/// ```
+/// use nordselect::Servers;
+///
+/// let data: Servers = Servers::dummy_data()
+///     .servers
+///     .into_iter()
+///     .filter(|server| server.flag == "NL")
+///     .collect();
+/// assert!(data.iter().all(|server| server.flag == "NL"));
+/// ```
+impl FromIterator<Server> for Servers {
+    fn from_iter<I: IntoIterator<Item = Server>>(servers: I) -> Servers {
+        Servers::from(Vec::from_iter(servers))
+    }
+}
+
+/// Functions to build and read data from the Servers.
 impl Servers {
-    /// Applies the given filter on this serverlist.
-    pub fn filter(&mut self, filter: &dyn Filter) {
-        (&mut self.servers).retain(|server| filter.filter(&server))
+    /// Creates a Servers by reading the given text.
+    pub(crate) fn from_txt(txt: &str) -> Result<Servers, serde_json::Error> {
+        Self::from_json_slice(txt.as_bytes())
     }
 
-    /// Sorts the servers using a Sorter. The sort is unstable.
-    pub fn sort(&mut self, sorter: &dyn Sorter) {
-        (&mut self.servers).sort_unstable_by(|x, y| sorter.sort(x, y));
+    /// Creates a Servers from the bytes of a response of the legacy API.
+    fn from_json_slice(bytes: &[u8]) -> Result<Servers, serde_json::Error> {
+        let list: ApiServerList = serde_json::from_slice(bytes)?;
+        Ok(Servers::from(list.0))
     }
 
-    /// Removes all but the `max` best servers at the moment. Does nothing if there are less
-    /// servers.
+    /// Creates a Servers by reading the response of the legacy API from the given reader. The
+    /// servers are converted while they are read, so the response is never kept in memory as a
+    /// whole.
+    fn from_json_reader<R: Read>(reader: R) -> Result<Servers, serde_json::Error> {
+        Self::from_json_reader_with(reader, false)
+    }
+
+    /// Like [from_json_reader](#method.from_json_reader), but also keeps the JSON object of every
+    /// server in `Server::raw` if `keep_raw` is set.
+    pub(crate) fn from_json_reader_with<R: Read>(
+        reader: R,
+        keep_raw: bool,
+    ) -> Result<Servers, serde_json::Error> {
+        let reader = BufReader::new(reader);
+        let servers = if keep_raw {
+            serde_json::from_reader::<_, RawApiServerList>(reader)?.0
+        } else {
+            serde_json::from_reader::<_, ApiServerList>(reader)?.0
+        };
+        Ok(Servers::from(servers))
+    }
+
+    /// Reads the list of servers from a reader, such as stdin or a decompressor. The data should
+    /// be a response of the [legacy API](constant.API_URL.html). The servers are sorted by domain.
     ///
-    /// 'Best' servers are defined by the filters and sorters that should have been applied before
-    /// calling this function.
-    pub fn cut(&mut self, max: usize) {
-        self.servers.truncate(max);
+    /// Returns an error on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// use std::fs::File;
+    ///
+    /// // If this fails, run `dummydata.sh` from the crate root.
+    /// let data = Servers::from_reader(File::open("dummydata").unwrap()).unwrap();
+    /// assert!(data.perfect_server().is_some());
+    /// ```
+    pub fn from_reader<R: Read>(reader: R) -> Result<Servers, Box<dyn std::error::Error>> {
+        Ok(Self::from_json_reader(reader)?.by_domain())
+    }
+
+    /// Sorts freshly read servers by domain, so the result does not depend on the order of the
+    /// API. Lists of which the order matters, such as recommendations, are not sorted.
+    pub(crate) fn by_domain(mut self) -> Servers {
+        self.sort_by_domain();
+        self
+    }
+
+    /// Creates a Servers by reading the given text, in the format of the v1 API.
+    fn from_txt_v1(txt: &str) -> Result<Servers, Box<dyn std::error::Error>> {
+        let api_servers: Vec<ApiV1Server> = serde_json::from_str(&txt)?;
+
+        Ok(Servers::from(Vec::from_iter(
+            api_servers
+                .into_iter()
+                .map(|api_server| Server::from(api_server)),
+        )))
+    }
+
+    /// Downloads the list of servers from the v1 API. Returns an error on failure.
+    ///
+    /// The v1 API provides more information than the legacy one used by
+    /// [from_api](#method.from_api), such as the city of every server. Search keywords are not
+    /// provided by the v1 API. The servers are sorted by domain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let data = nordselect::Servers::from_api_v1();
+    /// assert!(data.is_ok());
+    /// ```
+    pub fn from_api_v1() -> Result<Servers, Box<dyn std::error::Error>> {
+        Ok(Self::from_txt_v1(&Self::fetch(API_V1_URL)?)?.by_domain())
+    }
+
+    /// Downloads at most `limit` servers recommended by NordVPN. Returns an error on failure.
+    ///
+    /// The servers are sorted by NordVPN, with the best server first. Servers can be filtered
+    /// afterwards, but sorting is not required.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let data = nordselect::Servers::from_recommendations(5).unwrap();
+    /// assert!(data.perfect_server().is_some());
+    /// ```
+    pub fn from_recommendations(limit: usize) -> Result<Servers, Box<dyn std::error::Error>> {
+        Self::from_txt_v1(&Self::fetch(&recommendations_url(limit, None))?)
+    }
+
+    /// Downloads at most `limit` servers recommended by NordVPN in the country with the given
+    /// flag, noted according to [ISO 3166-1 alpha-2](https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2).
+    /// The servers are sorted by NordVPN, like [from_recommendations](#method.from_recommendations).
+    ///
+    /// Returns an error if the flag is not a known country, without sending any request. Also
+    /// returns an error if NordVPN has no servers in the country, or on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    ///
+    /// let data = Servers::from_recommendations_for_country("NL", 5).unwrap();
+    /// assert!(data.servers.iter().all(|server| server.flag == "NL"));
+    /// ```
+    pub fn from_recommendations_for_country(
+        flag: &str,
+        limit: usize,
+    ) -> Result<Servers, Box<dyn std::error::Error>> {
+        let id = Self::fetch_country_id(flag)?;
+        Self::from_txt_v1(&Self::fetch(&recommendations_url(limit, Some(id)))?)
+    }
+
+    /// Downloads the servers in the country with the given flag from the v1 API, noted according
+    /// to [ISO 3166-1 alpha-2](https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2). Only these
+    /// servers are downloaded, which is a lot faster than downloading all servers using
+    /// [from_api_v1](#method.from_api_v1) and applying a `CountryFilter`.
+    ///
+    /// Returns an error if the flag is not a known country, without sending any request. Also
+    /// returns an error if NordVPN has no servers in the country, or on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    ///
+    /// let data = Servers::from_api_for_country("NL").unwrap();
+    /// assert!(data.servers.iter().all(|server| server.flag == "NL"));
+    ///
+    /// assert!(Servers::from_api_for_country("XX").is_err());
+    /// ```
+    pub fn from_api_for_country(flag: &str) -> Result<Servers, Box<dyn std::error::Error>> {
+        let id = Self::fetch_country_id(flag)?;
+        let data = Self::from_txt_v1(&Self::fetch(&format!(
+            "{}&filters[country_id]={}",
+            API_V1_URL, id
+        ))?)?;
+        Ok(data.by_domain())
+    }
+
+    /// Downloads the list of servers from the API. Returns an error on failure.
+    ///
+    /// The servers are sorted by domain, so the result does not depend on the order of the API.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let data = nordselect::Servers::from_api();
+    /// assert!(data.is_ok());
+    /// ```
+    pub fn from_api() -> Result<Servers, Box<dyn std::error::Error>> {
+        Ok(Self::from_api_with_config(&ApiConfig::default())?)
+    }
+
+    /// Downloads the list of servers from the API, using the given client. Useful to reuse a
+    /// client configured with a proxy, user agent or timeouts. Temporary failures are retried
+    /// using the default [Retry](../api/struct.Retry.html).
+    ///
+    /// Returns an error on failure, telling whether the request failed or the response could not
+    /// be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate nordselect;
+    /// extern crate reqwest;
+    ///
+    /// let client = reqwest::Client::new();
+    /// let data = nordselect::Servers::from_api_with_client(&client).unwrap();
+    /// assert!(data.perfect_server().is_some());
+    /// ```
+    pub fn from_api_with_client(client: &reqwest::Client) -> Result<Servers, ApiError> {
+        Self::from_api_with(client, &Retry::default(), API_URL, false)
+    }
+
+    /// Downloads the list of servers from the API, using the given client and retries.
+    fn from_api_with(
+        client: &reqwest::Client,
+        retry: &Retry,
+        url: &str,
+        keep_raw: bool,
+    ) -> Result<Servers, ApiError> {
+        // The response is parsed while it is downloaded, to keep the memory usage low.
+        let servers = api::get(client, retry, url, |response| {
+            Self::from_json_reader_with(response, keep_raw)
+        })?;
+        Ok(servers.by_domain())
+    }
+
+    /// Downloads the list of servers from the API without blocking. The future fails if the
+    /// request fails or the response could not be parsed. Failed requests are not retried.
+    ///
+    /// Only available with the `async` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate futures;
+    /// extern crate nordselect;
+    ///
+    /// use futures::Future;
+    /// use nordselect::Servers;
+    ///
+    /// let data = Servers::from_api_async().wait().unwrap();
+    /// assert!(data.perfect_server().is_some());
+    /// ```
+    #[cfg(feature = "async")]
+    pub fn from_api_async() -> impl Future<Item = Servers, Error = ApiError> {
+        reqwest::async::Client::new()
+            .get(API_URL)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(api::single_attempt)
+            .and_then(|response| Self::from_stream(response.into_body()))
+            .map(Servers::by_domain)
+    }
+
+    /// Reads the response of the legacy API from the given stream of chunks.
+    #[cfg(feature = "async")]
+    fn from_stream<S>(body: S) -> impl Future<Item = Servers, Error = ApiError>
+    where
+        S: Stream<Error = reqwest::Error>,
+        S::Item: AsRef<[u8]>,
+    {
+        body.fold(Vec::new(), |mut bytes, chunk| {
+            bytes.extend_from_slice(chunk.as_ref());
+            Ok::<_, reqwest::Error>(bytes)
+        })
+        .map_err(api::single_attempt)
+        .and_then(|bytes| {
+            Self::from_json_slice(&bytes).map_err(|error| ApiError::Parse { attempts: 1, error })
+        })
+    }
+
+    /// Downloads the list of servers from the API, retrying temporary failures as configured.
+    /// Returns an error on failure.
+    ///
+    /// [from_api](#method.from_api) uses the [default](../api/struct.Retry.html) configuration.
+    /// When all attempts fail, the [ApiError](../api/enum.ApiError.html) tells how many attempts
+    /// were made, see [ApiError::attempts](../api/enum.ApiError.html#method.attempts).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::api::Retry;
+    /// use nordselect::Servers;
+    /// use std::time::Duration;
+    ///
+    /// let retry = Retry {
+    ///     attempts: 5,
+    ///     base_delay: Duration::from_secs(1),
+    /// };
+    /// match Servers::from_api_with_retry(&retry) {
+    ///     Ok(data) => assert!(data.perfect_server().is_some()),
+    ///     Err(error) => panic!("failed after {} attempts: {}", error.attempts(), error),
+    /// }
+    /// ```
+    pub fn from_api_with_retry(retry: &Retry) -> Result<Servers, ApiError> {
+        Self::from_api_with_config(&ApiConfig::new().retry(retry.clone()))
+    }
+
+    /// Downloads the list of servers from the API, using the given timeouts, retries, proxy, fields
+    /// and URL. Returns an error on failure.
+    ///
+    /// When the config has a [cache_ttl](../api/struct.ApiConfig.html#method.cache_ttl), the list
+    /// is read from and written to the [cache](../cache/index.html).
+    ///
+    /// When the API does not respond in time, the error is `ApiError::Timeout`. Callers can use
+    /// this to fall back to a cached list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::api::{ApiConfig, ApiError};
+    /// use nordselect::Servers;
+    /// use std::time::Duration;
+    ///
+    /// let config = ApiConfig::new()
+    ///     .timeout(Duration::from_secs(5))
+    ///     .minimal_fields(true);
+    /// match Servers::from_api_with_config(&config) {
+    ///     Ok(data) => assert!(data.perfect_server().is_some()),
+    ///     Err(ApiError::Timeout { .. }) => println!("The API is too slow"),
+    ///     Err(error) => panic!("{}", error),
+    /// }
+    /// ```
+    pub fn from_api_with_config(config: &ApiConfig) -> Result<Servers, ApiError> {
+        if let Some((dir, ttl)) = config.cache() {
+            return Cache::new(dir)
+                .keep_raw(config.keep_raw)
+                .load_with_config(config, ttl, false);
+        }
+        let client = config.client()?;
+        Self::from_api_with(&client, &config.retry, &config.url(), config.keep_raw)
+    }
+
+    /// Downloads the list of servers from the given URL, which should serve the same data as the
+    /// [legacy API](constant.API_URL.html). Useful for caching mirrors or local test servers.
+    ///
+    /// Returns an error if the URL is invalid, the request fails, the server does not respond with
+    /// a successful status code or the response cannot be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    ///
+    /// assert!(Servers::from_url("not a url").is_err());
+    /// assert!(Servers::from_url("https://nordvpn.com/api/server").is_ok());
+    /// ```
+    pub fn from_url(url: &str) -> Result<Servers, Box<dyn std::error::Error>> {
+        Ok(Self::from_txt(&Self::fetch(url)?)?.by_domain())
+    }
+
+    /// Downloads the identifier of the country with the given flag in the v1 API. Returns an error
+    /// if the flag is not a known country, without sending any request, or if NordVPN has no
+    /// servers in the country.
+    fn fetch_country_id(flag: &str) -> Result<u32, Box<dyn std::error::Error>> {
+        if Continent::from_flag(flag).is_none() {
+            return Err(From::from(format!("unknown country: \"{}\"", flag)));
+        }
+        let id = country_id(&Self::fetch(API_COUNTRIES_URL)?, flag)?
+            .ok_or_else(|| format!("NordVPN has no servers in \"{}\"", flag))?;
+        Ok(id)
+    }
+
+    /// Downloads the body of the given URL, using the default timeouts and retries. Returns an
+    /// error on failure or when the server does not respond with a successful status code.
+    fn fetch(url: &str) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(ApiConfig::default().fetch(url)?)
+    }
+
+    /// Returns the list of servers from the [cache](../cache/index.html) in the default cache
+    /// directory if it is younger than `ttl`. Otherwise, the list is downloaded from the API and
+    /// cached. Returns an error on failure.
+    ///
+    /// If there is no default cache directory, the list is always downloaded. Use a
+    /// [Cache](../cache/struct.Cache.html) to choose the directory or to force a refresh.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// let data = nordselect::Servers::from_api_cached(Duration::from_secs(300));
+    /// assert!(data.is_ok());
+    /// ```
+    pub fn from_api_cached(ttl: Duration) -> Result<Servers, Box<dyn std::error::Error>> {
+        match Cache::default_dir() {
+            Some(dir) => Cache::new(dir).load(ttl, false),
+            None => Self::from_api(),
+        }
+    }
+
+    /// Returns the data, fetched out of the `dummydata` file, generated using `dummydata.sh`.
+    ///
+    /// Use this only for debugging, testing and benchmarking.
+    ///
+    /// # Examples
+    /// ```
+    /// // If this fails, run `dummydata.sh` from the crate root.
+    /// nordselect::Servers::dummy_data();
+    /// ```
+    pub fn dummy_data() -> Servers {
+        Self::from_file("dummydata").unwrap()
+    }
+
+    /// Returns a fixed list of 30 servers in 10 countries, parsed from a response of the
+    /// [legacy API](constant.API_URL.html) included in this crate. The servers cover every
+    /// category and protocol, including servers without TCP or UDP support. Useful to test code
+    /// using this crate without network access.
+    ///
+    /// Only available with the `test-util` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::{filters, Servers};
+    ///
+    /// let mut data = Servers::fixture();
+    /// data.filter(&filters::CountryFilter::from("NL"));
+    /// assert_eq!(data.servers.len(), 5);
+    /// ```
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn fixture() -> Servers {
+        Self::from_txt(include_str!("../fixtures/servers.json")).expect("the fixture is valid")
+    }
+
+    /// Reads the list of servers from a file, containing a response of the
+    /// [legacy API](constant.API_URL.html). Useful to work offline.
+    ///
+    /// Returns an error on failure. If the file could not be read, the error is an
+    /// `std::io::Error`. If the contents are invalid, it is a `serde_json::Error`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    ///
+    /// // If this fails, run `dummydata.sh` from the crate root.
+    /// let data = Servers::from_file("dummydata").unwrap();
+    /// assert!(data.perfect_server().is_some());
+    ///
+    /// let error = Servers::from_file("nonexistent").err().unwrap();
+    /// assert!(error.downcast_ref::<std::io::Error>().is_some());
+    /// ```
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Servers, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(&path)?;
+        let mut servers = Self::from_txt(&text)?.by_domain();
+        // The file was written when the servers were downloaded.
+        if let Ok(modified) = std::fs::metadata(&path).and_then(|metadata| metadata.modified()) {
+            servers.fetched_at = modified;
+        }
+        Ok(servers)
+    }
+
+    /// Writes the servers to a file in a compact binary format, which can be read a lot faster
+    /// than the API response using [load_snapshot](#method.load_snapshot).
+    ///
+    /// Returns an error on failure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    ///
+    /// let name = format!("nordselect_doc_snapshot_{}", std::process::id());
+    /// let path = std::env::temp_dir().join(name);
+    /// let data = Servers::dummy_data();
+    /// data.save_snapshot(&path).unwrap();
+    ///
+    /// assert_eq!(Servers::load_snapshot(&path).unwrap().servers, data.servers);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn save_snapshot<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn std::error::Error>> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&[SNAPSHOT_VERSION])?;
+        bincode::serialize_into(&mut file, &(self.fetched_at, &self.servers))?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Reads the servers from a file written by [save_snapshot](#method.save_snapshot).
+    ///
+    /// Returns an error on failure. Snapshots written by another version of this library may use
+    /// another format. These are not read, and the error tells to download the list again.
+    pub fn load_snapshot<P: AsRef<Path>>(path: P) -> Result<Servers, Box<dyn std::error::Error>> {
+        let mut file = BufReader::new(File::open(path)?);
+        let mut version = [0];
+        file.read_exact(&mut version)?;
+        if version[0] != SNAPSHOT_VERSION {
+            return Err(Box::new(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "the snapshot has format version {} instead of {}, download the list again",
+                    version[0], SNAPSHOT_VERSION
+                ),
+            )));
+        }
+        let (fetched_at, servers) = bincode::deserialize_from(file)?;
+        Ok(Servers {
+            servers,
+            fetched_at,
+        })
+    }
+
+    /// Returns how long ago the servers were downloaded. A download time in the future, e.g. after
+    /// the clock was changed, is treated as now.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// use std::time::Duration;
+    ///
+    /// let data = Servers::from_api().unwrap();
+    /// assert!(data.age() < Duration::from_secs(60));
+    /// ```
+    pub fn age(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.fetched_at)
+            .unwrap_or(Duration::from_secs(0))
+    }
+
+    /// Returns whether the servers were downloaded more than `max_age` ago, in which case their
+    /// load is probably outdated.
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        self.age() > max_age
+    }
+
+    /// Returns a set with all the flags (countries) in this set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// let data = Servers::dummy_data();
+    ///
+    /// assert!(data.flags().contains("BE"));
+    /// assert!(data.flags().contains("US"));
+    ///
+    /// assert!(!data.flags().contains("XK")); // No servers in Kosovo
+    /// assert!(!data.flags().contains("EU")); // The EU is not a country
+    /// ```
+    pub fn flags(&self) -> HashSet<&str> {
+        HashSet::from_iter(self.servers.iter().map(|server| server.flag.as_ref()))
+    }
+
+    /// Returns statistics about the load of the servers in every country. The keys are the flags in
+    /// uppercase.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// let data = Servers::dummy_data();
+    ///
+    /// let stats = data.load_stats_by_country();
+    /// let belgium = &stats["BE"];
+    /// assert!(belgium.min as f64 <= belgium.mean && belgium.mean <= belgium.max as f64);
+    /// ```
+    pub fn load_stats_by_country(&self) -> HashMap<String, LoadStats> {
+        self.by_country()
+            .into_iter()
+            .map(|(flag, servers)| {
+                let loads = servers.iter().map(|server| server.load);
+                let total: u64 = loads.clone().map(u64::from).sum();
+                let stats = LoadStats {
+                    count: servers.len(),
+                    min: loads.clone().min().unwrap_or(0),
+                    max: loads.max().unwrap_or(0),
+                    mean: total as f64 / servers.len() as f64,
+                };
+                (flag, stats)
+            })
+            .collect()
+    }
+
+    /// Returns a set with all the categories of the servers in this set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::{Servers, ServerCategory};
+    /// let data = Servers::dummy_data();
+    ///
+    /// assert!(data.categories().contains(&ServerCategory::Standard));
+    /// ```
+    pub fn categories(&self) -> HashSet<ServerCategory> {
+        self.servers
+            .iter()
+            .flat_map(|server| server.categories.iter().cloned())
+            .collect()
+    }
+
+    /// Groups the servers by their country. The keys are the flags in uppercase, and every group
+    /// keeps the order of the servers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// let data = Servers::dummy_data();
+    ///
+    /// for (flag, servers) in data.by_country() {
+    ///     println!("{}: {} servers", flag, servers.len());
+    /// }
+    /// ```
+    pub fn by_country(&self) -> HashMap<String, Vec<&Server>> {
+        let mut countries: HashMap<String, Vec<&Server>> = HashMap::new();
+        for server in &self.servers {
+            countries
+                .entry(server.flag.to_ascii_uppercase())
+                .or_insert_with(Vec::new)
+                .push(server);
+        }
+        countries
+    }
+
+    /// Returns the best server of every country, which is the first one in the current order. The
+    /// keys are the flags in uppercase. Call this after sorting, e.g. with a `LoadSorter` or a
+    /// `PingSorter`. The list is not modified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// use nordselect::sorters::LoadSorter;
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// data.sort_stable(&LoadSorter);
+    /// let best = data.best_per_country();
+    /// assert_eq!(best.len(), data.country_counts().len());
+    /// ```
+    pub fn best_per_country(&self) -> HashMap<String, Server> {
+        let mut countries = HashMap::new();
+        for server in &self.servers {
+            countries
+                .entry(server.flag.to_ascii_uppercase())
+                .or_insert_with(|| server.clone());
+        }
+        countries
+    }
+
+    /// Returns a new list with at most `n` servers of every country: the first ones in the current
+    /// order, which is kept. Pinging such a sample measures every country, instead of only the
+    /// country that has most of the least loaded servers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// use nordselect::sorters::LoadSorter;
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// data.sort_stable(&LoadSorter);
+    /// let sample = data.sample_per_country(2);
+    /// assert!(sample.country_counts().values().all(|&count| count <= 2));
+    /// ```
+    pub fn sample_per_country(&self, n: usize) -> Servers {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        let servers = self
+            .servers
+            .iter()
+            .filter(|server| {
+                let count = counts.entry(server.flag.to_ascii_uppercase()).or_insert(0);
+                *count += 1;
+                *count <= n
+            })
+            .cloned()
+            .collect();
+        Servers {
+            servers,
+            fetched_at: self.fetched_at,
+        }
+    }
+
+    /// Returns the number of servers in every country. The keys are the flags in uppercase.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// let data = Servers::dummy_data();
+    ///
+    /// assert!(data.country_counts()["BE"] > 0);
+    /// ```
+    pub fn country_counts(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for server in &self.servers {
+            *counts.entry(server.flag.to_ascii_uppercase()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Returns the best server, according to the given values. This should be called after all the
+    /// filters have been applied.
+    ///
+    /// Returns `None` if no `Server` fullfills all your needs.
+    ///
+    /// This is the first server of the list, so it depends on how the list was sorted. Use
+    /// [best](#method.best) to select a server regardless of the order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::{Servers, filters};
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// data.filter(&filters::CountryFilter::from_code("".to_string()));
+    /// assert_eq!(data.perfect_server(), None);
+    ///
+    /// let mut data = Servers::dummy_data();
+    /// data.filter(&filters::CountryFilter::from_code("BE".to_string()));
+    /// assert!(data.perfect_server().is_some());
+    /// ```
+    pub fn perfect_server(&self) -> Option<Server> {
+        self.perfect_servers(1).into_iter().next()
+    }
+
+    /// Returns the least loaded server, regardless of the order of the list. Servers with the same
+    /// load are ordered on their latency if `pings` is given, where servers that were not pinged
+    /// come last, and then alphabetically on their short name. The choice is thus the same for
+    /// every order of the API.
+    ///
+    /// Returns `None` if there are no servers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// let best = data.best(None).cloned();
+    /// data.sort_best(None);
+    /// assert_eq!(data.perfect_server(), best);
+    /// ```
+    pub fn best(&self, pings: Option<&PingSorter>) -> Option<&Server> {
+        self.servers.iter().min_by(|a, b| cmp_best(a, b, pings))
+    }
+
+    /// Returns the `n` best servers, in order, to fall back on the next one when connecting to a
+    /// server fails. This should be called after all the filters have been applied.
+    ///
+    /// Returns fewer servers if less than `n` servers fullfill all your needs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::{Servers, filters};
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// data.filter(&filters::CountryFilter::from_code("BE".to_string()));
+    /// let candidates = data.perfect_servers(3);
+    /// assert!(candidates.len() <= 3);
+    /// assert_eq!(candidates.first(), data.perfect_server().as_ref());
+    /// ```
+    pub fn perfect_servers(&self, n: usize) -> Vec<Server> {
+        self.servers.iter().take(n).cloned().collect()
+    }
+
+    /// Returns a new list with the servers that pass the given filter, leaving this list intact.
+    /// Useful to try several filters on the same list without downloading it again.
+    ///
+    /// The kept servers are cloned, so both lists use memory until one of them is dropped. Use
+    /// [filter](#method.filter) if the original list is no longer needed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// use nordselect::filters::CountryFilter;
+    /// let data = Servers::dummy_data();
+    ///
+    /// let belgian = data.filtered(&CountryFilter::from("BE"));
+    /// let dutch = data.filtered(&CountryFilter::from("NL"));
+    /// assert!(belgian.len() + dutch.len() <= data.len());
+    /// ```
+    pub fn filtered(&self, filter: &dyn Filter) -> Servers {
+        Servers {
+            servers: self
+                .servers
+                .iter()
+                .filter(|server| filter.filter(server))
+                .cloned()
+                .collect(),
+            fetched_at: self.fetched_at,
+        }
+    }
+
+    /// Compares this list with an older list, returning the servers that were added and those that
+    /// were removed, in the order of their list. Servers are compared by their domain, so servers
+    /// of which only the load changed are in neither list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// let old = Servers::dummy_data();
+    /// let mut new = old.clone();
+    ///
+    /// let removed = new.servers.remove(0);
+    /// let (added, gone) = new.diff(&old);
+    /// assert!(added.is_empty());
+    /// assert_eq!(gone, vec![&removed]);
+    /// ```
+    pub fn diff<'a>(&'a self, other: &'a Servers) -> (Vec<&'a Server>, Vec<&'a Server>) {
+        let ours: HashSet<&Server> = self.servers.iter().collect();
+        let theirs: HashSet<&Server> = other.servers.iter().collect();
+        let added = self
+            .servers
+            .iter()
+            .filter(|server| !theirs.contains(server))
+            .collect();
+        let removed = other
+            .servers
+            .iter()
+            .filter(|server| !ours.contains(server))
+            .collect();
+        (added, removed)
+    }
+
+    /// Splits the list in the servers that pass the given filter and those that do not, leaving
+    /// this list intact. Both lists keep the order of the servers, so the second one can be used as
+    /// a fallback for the first one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::{Servers, ServerCategory};
+    /// use nordselect::filters::CategoryFilter;
+    /// let data = Servers::dummy_data();
+    ///
+    /// // Prefer P2P servers, but keep the others in case none of them responds.
+    /// let (p2p, others) = data.partition(&CategoryFilter::from(ServerCategory::P2P));
+    /// assert_eq!(p2p.len() + others.len(), data.len());
+    /// ```
+    pub fn partition(&self, filter: &dyn Filter) -> (Servers, Servers) {
+        let (matching, others): (Vec<Server>, Vec<Server>) = self
+            .servers
+            .iter()
+            .cloned()
+            .partition(|server| filter.filter(server));
+        (
+            Servers {
+                servers: matching,
+                fetched_at: self.fetched_at,
+            },
+            Servers {
+                servers: others,
+                fetched_at: self.fetched_at,
+            },
+        )
+    }
+
+    /// Returns a random server among the `n` best servers, or among all servers if there are less.
+    /// This spreads users over several servers, instead of always choosing the least loaded one.
+    ///
+    /// The list is not modified. Returns `None` if there are no servers or `n` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// use nordselect::sorters::LoadSorter;
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// data.sort(&LoadSorter);
+    /// let server = data.pick_random_top(5).unwrap();
+    /// assert!(data.perfect_servers(5).contains(&server));
+    /// ```
+    pub fn pick_random_top(&self, n: usize) -> Option<Server> {
+        self.pick_random_top_seeded(n, random_seed())
+    }
+
+    /// Like [pick_random_top](#method.pick_random_top), but always picks the same server for the
+    /// same seed and list. Useful for reproducible tests.
+    pub fn pick_random_top_seeded(&self, n: usize, seed: u64) -> Option<Server> {
+        let candidates = std::cmp::min(n, self.servers.len());
+        if candidates == 0 {
+            return None;
+        }
+        let index = SplitMix64(seed).below(candidates);
+        Some(self.servers[index].clone())
+    }
+
+    /// Returns an iterator over the servers, in their current order: after filtering and sorting,
+    /// the best server comes first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::{Servers, filters};
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// data.filter(&filters::CountryFilter::from_code("BE".to_string()));
+    /// let loads: Vec<u8> = data.iter().map(|server| server.load).collect();
+    /// assert!(loads.iter().all(|&load| load <= 100));
+    /// ```
+    pub fn iter(&self) -> std::slice::Iter<Server> {
+        self.servers.iter()
+    }
+
+    /// Returns the server with the given domain, such as `de750.nordvpn.com`, or short name, such
+    /// as `de750`. Names are compared case-insensitively. Returns `None` if the server is not in
+    /// the list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// let data = Servers::dummy_data();
+    ///
+    /// let server = data.perfect_server().unwrap();
+    /// assert_eq!(data.find(&server.domain.to_uppercase()), Some(&server));
+    /// assert_eq!(data.find("xx0.nordvpn.com"), None);
+    /// ```
+    pub fn find(&self, name_or_domain: &str) -> Option<&Server> {
+        let name = name_or_domain.trim();
+        self.servers.iter().find(|server| has_name(server, name))
+    }
+
+    /// Serializes the servers as a JSON array, e.g. to pass the selected servers to other tools.
+    /// Categories and features use the names of the API.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// data.cut(1);
+    /// let json = data.to_json().unwrap();
+    /// assert!(json.starts_with("[{"));
+    /// ```
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&self.servers)
+    }
+
+    /// Writes the servers as JSON lines: one JSON object per line, in the same format as
+    /// [to_json](#method.to_json). This is easy to process using tools like `jq` and `grep`.
+    ///
+    /// Returns an error if writing fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// let data = Servers::dummy_data();
+    ///
+    /// let mut lines = Vec::new();
+    /// data.to_json_lines(&mut lines).unwrap();
+    /// assert_eq!(String::from_utf8(lines).unwrap().lines().count(), data.len());
+    /// ```
+    pub fn to_json_lines<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = BufWriter::new(writer);
+        for server in &self.servers {
+            serde_json::to_writer(&mut writer, server)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()
+    }
+
+    /// Writes the servers as CSV, with a header row and one row per server. The columns are the
+    /// domain, country, load, categories (separated by semicolons) and support for the main
+    /// protocols.
+    ///
+    /// Returns an error if writing fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// let data = Servers::dummy_data();
+    ///
+    /// let mut csv = Vec::new();
+    /// data.to_csv(&mut csv).unwrap();
+    /// assert_eq!(String::from_utf8(csv).unwrap().lines().count(), data.len() + 1);
+    /// ```
+    pub fn to_csv<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = BufWriter::new(writer);
+        writeln!(writer, "{}", CSV_COLUMNS.join(","))?;
+        for server in &self.servers {
+            let categories: Vec<String> = server
+                .categories
+                .iter()
+                .map(|category| category.to_string())
+                .collect();
+            let features = &server.features;
+            let cells = [
+                csv_cell(&server.domain),
+                csv_cell(&server.flag),
+                server.load.to_string(),
+                csv_cell(&categories.join(";")),
+                features.openvpn_udp.to_string(),
+                features.openvpn_tcp.to_string(),
+                features.ikev2.to_string(),
+                features.wireguard_udp.to_string(),
+                features.openvpn_xor_udp.to_string(),
+                features.openvpn_xor_tcp.to_string(),
+            ];
+            writeln!(writer, "{}", cells.join(","))?;
+        }
+        writer.flush()
+    }
+
+    /// Returns the number of servers, e.g. to relax the filters when too few servers are left.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::{Servers, filters};
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// data.filter(&filters::CountryFilter::from_code("BE".to_string()));
+    /// if data.len() < 3 {
+    ///     println!("Only {} servers left", data.len());
+    /// }
+    /// ```
+    pub fn len(&self) -> usize {
+        self.servers.len()
+    }
+
+    /// Returns whether no servers are left.
+    pub fn is_empty(&self) -> bool {
+        self.servers.is_empty()
+    }
+
+    /// Returns the servers as a slice, in their current order, to inspect them without cloning.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::{Servers, filters};
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// data.filter(&filters::CountryFilter::from_code("BE".to_string()));
+    /// for server in data.servers() {
+    ///     println!("{}", server.domain);
+    /// }
+    /// assert_eq!(data.servers().first(), data.perfect_server().as_ref());
+    /// ```
+    pub fn servers(&self) -> &[Server] {
+        &self.servers
+    }
+}
+
+/// Iterates over the servers in their current order, consuming the list.
+///
+/// # Examples
+///
+/// ```
+/// use nordselect::Servers;
+/// let data = Servers::dummy_data();
+///
+/// let domains: Vec<String> = data.into_iter().map(|server| server.domain).collect();
+/// assert!(!domains.is_empty());
+/// ```
+impl IntoIterator for Servers {
+    type Item = Server;
+    type IntoIter = std::vec::IntoIter<Server>;
+
+    fn into_iter(self) -> std::vec::IntoIter<Server> {
+        self.servers.into_iter()
+    }
+}
+
+/// Iterates over the servers in their current order.
+///
+/// # Examples
+///
+/// ```
+/// use nordselect::Servers;
+/// let data = Servers::dummy_data();
+///
+/// for server in &data {
+///     println!("{}: {}%", server.domain, server.load);
+/// }
+/// ```
+impl<'a> IntoIterator for &'a Servers {
+    type Item = &'a Server;
+    type IntoIter = std::slice::Iter<'a, Server>;
+
+    fn into_iter(self) -> std::slice::Iter<'a, Server> {
+        self.servers.iter()
+    }
+}
+
+/// Parses a response of the [legacy API](constant.API_URL.html).
+///
+/// # Examples
+///
+/// ```
+/// use nordselect::Servers;
+///
+/// let data: Servers = "[]".parse().unwrap();
+/// assert_eq!(data.perfect_server(), None);
+/// ```
+impl FromStr for Servers {
+    type Err = Box<dyn std::error::Error>;
+
+    fn from_str(txt: &str) -> Result<Servers, Box<dyn std::error::Error>> {
+        Ok(Self::from_txt(txt)?.by_domain())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+/// A protocol to connect to the VPN server.
+pub enum Protocol {
+    /// OpenVPN over the [User Datagram Protocol](https://en.wikipedia.org/wiki/User_Datagram_Protocol)
+    Udp,
+    /// OpenVPN over the [Transmission Control Protocol](https://en.wikipedia.org/wiki/Transmission_Control_Protocol)
+    Tcp,
+    /// The older Point-to-Point Tunneling Protocol
+    ///
+    /// **Warning**: this protocol is considered unsafe. Usage is discouraged.
+    ///
+    /// From the NordVPN site:
+    /// > Although technically you can use the L2TP/PPTP protocol, it has serious security flaws.
+    /// > Whenever possible, we recommend choosing OpenVPN or IKEv2/IPSec instead.
+    Pptp,
+    /// The Layer 2 Tunneling Protocol
+    ///
+    /// **Warning**: this protocol is considered unsafe. Usage is discouraged.
+    ///
+    /// From the NordVPN site:
+    /// > Although technically you can use the L2TP/PPTP protocol, it has serious security flaws.
+    /// > Whenever possible, we recommend choosing OpenVPN or IKEv2/IPSec instead.
+    L2tp,
+    /// OpenVPN over TCP with xor obfuscation, which helps in networks that block VPN traffic.
+    ///
+    /// This is not the same as the `ServerCategory::Obfuscated` category.
+    OpenVPNXTcp,
+    /// OpenVPN over UDP with xor obfuscation, which helps in networks that block VPN traffic.
+    ///
+    /// This is not the same as the `ServerCategory::Obfuscated` category.
+    OpenVPNXUdp,
+    /// Support for the SOCKS protocol.
+    Socks,
+    /// Support for a proxy with CyberSec
+    CyberSecProxy,
+    /// Support for a proxy with SSL
+    SslProxy,
+    /// Support for a proxy with CyberSec and SSL
+    ///
+    /// Only servers offering the combined proxy are matched. A server with a separate CyberSec
+    /// proxy and SSL proxy does not qualify.
+    CyberSecSslProxy,
+    /// Use the server as a proxy
+    Proxy,
+    /// WireGuard over UDP, which NordVPN calls NordLynx.
+    ///
+    /// Servers only match if the API reports WireGuard support, which older responses do not.
+    WireGuardUdp,
+    /// The [IKEv2/IPSec](https://en.wikipedia.org/wiki/Internet_Key_Exchange) protocol
+    Ikev2,
+}
+
+/// All manipulations that will alter the servers.
+impl Servers {
+    /// Applies the given filter on this serverlist.
+    pub fn filter(&mut self, filter: &dyn Filter) {
+        (&mut self.servers).retain(|server| filter.filter(&server))
+    }
+
+    /// Applies all given filters on this serverlist in a single pass: only servers that pass every
+    /// filter are kept. Returns the number of servers left.
+    ///
+    /// This has the same result as applying the filters one after another, or as applying an
+    /// [AndFilter](../filters/struct.AndFilter.html) of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::{Servers, ServerCategory};
+    /// use nordselect::filters::{CategoryFilter, CountryFilter, Filter};
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// let filters: Vec<Box<dyn Filter>> = vec![
+    ///     Box::new(CountryFilter::from("BE")),
+    ///     Box::new(CategoryFilter::from(ServerCategory::Standard)),
+    /// ];
+    /// let left = data.filter_all(&filters);
+    /// assert_eq!(left, data.len());
+    /// ```
+    pub fn filter_all(&mut self, filters: &[Box<dyn Filter>]) -> usize {
+        self.servers
+            .retain(|server| filters.iter().all(|filter| filter.filter(server)));
+        self.servers.len()
+    }
+
+    /// Applies the given filter on this serverlist and returns how many servers were removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// use nordselect::filters::{CountryFilter, Filter};
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// let filter = CountryFilter::from("nl");
+    /// let removed = data.filter_verbose(&filter);
+    /// println!("{}: removed {}, kept {}", filter.describe(), removed, data.len());
+    /// ```
+    pub fn filter_verbose(&mut self, filter: &dyn Filter) -> usize {
+        let before = self.len();
+        self.filter(filter);
+        before - self.len()
+    }
+
+    /// Removes all servers with a dedicated IP, as these require a separate subscription. Servers
+    /// that are in the `Dedicated` category are removed, even if they are in other categories as
+    /// well.
+    ///
+    /// This is a shorthand for filtering with a
+    /// [NonDedicatedFilter](../filters/struct.NonDedicatedFilter.html).
+    pub fn remove_dedicated(&mut self) {
+        self.filter(&NonDedicatedFilter);
+    }
+
+    /// Keeps only servers in the `Standard` category. Without this, Tor, Double VPN or obfuscated
+    /// servers can be selected because of their low load, while they are often much slower.
+    ///
+    /// This is opt-in: call this only when the user did not ask for a specific category. Combined
+    /// with a `CategoryFilter` for another category, no servers would remain unless they are in
+    /// both categories. The CLI uses this behaviour when no category is given.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::{Servers, ServerCategory};
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// data.default_category_guard();
+    ///
+    /// assert!(data.perfect_server().unwrap().categories.contains(&ServerCategory::Standard));
+    /// ```
+    pub fn default_category_guard(&mut self) {
+        self.filter(&CategoryFilter::from(ServerCategory::Standard));
+    }
+
+    /// Shuffles the servers randomly, e.g. to spread users over servers that are equally good.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// use nordselect::sorters::LoadSorter;
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// // Pick one of the 10 least loaded servers.
+    /// data.sort(&LoadSorter);
+    /// data.cut(10);
+    /// data.shuffle();
+    /// assert!(data.perfect_server().is_some());
+    /// ```
+    pub fn shuffle(&mut self) {
+        self.shuffle_seeded(random_seed());
+    }
+
+    /// Like [shuffle](#method.shuffle), but always shuffles the same list in the same way for the
+    /// same seed. Useful for reproducible tests.
+    pub fn shuffle_seeded(&mut self, seed: u64) {
+        // The Fisher-Yates shuffle.
+        let mut rng = SplitMix64(seed);
+        for i in (1..self.servers.len()).rev() {
+            let j = rng.below(i + 1);
+            self.servers.swap(i, j);
+        }
+    }
+
+    /// Sorts the servers using a Sorter. The sort is unstable.
+    pub fn sort(&mut self, sorter: &dyn Sorter) {
+        (&mut self.servers).sort_unstable_by(|x, y| sorter.sort(x, y));
+    }
+
+    /// Sorts the servers using a Sorter, ordering servers that are equal according to the Sorter
+    /// on their short name. Unlike [sort](#method.sort), the order is the same on every run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// use nordselect::sorters::LoadSorter;
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// data.sort_stable(&LoadSorter);
+    /// ```
+    pub fn sort_stable(&mut self, sorter: &dyn Sorter) {
+        self.servers.sort_by(|a, b| cmp_stable(a, b, sorter));
+    }
+
+    /// Sorts the servers in the order used by [best](#method.best): on their load, then on their
+    /// latency if `pings` is given, then on their short name. The best server comes first, so it
+    /// is kept by [cut](#method.cut).
+    pub fn sort_best(&mut self, pings: Option<&PingSorter>) {
+        self.servers.sort_by(|a, b| cmp_best(a, b, pings));
+    }
+
+    /// Sorts the servers on the given ping results, fastest first. Servers that were not pinged or
+    /// did not answer never cause a panic: they are ordered after the pinged servers, on their
+    /// short name like servers with the same results.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// use nordselect::sorters::PingSorter;
+    /// use std::collections::HashMap;
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// // Only one server was pinged.
+    /// let pinged = data.servers[3].domain.clone();
+    /// let mut results = HashMap::new();
+    /// results.insert(pinged.clone(), 20_000);
+    /// data.sort_ping(&PingSorter::from(results));
+    /// assert_eq!(data.perfect_server().unwrap().domain, pinged);
+    /// ```
+    pub fn sort_ping(&mut self, pings: &PingSorter) {
+        self.sort_stable(pings);
+    }
+
+    /// Pings the first `n` servers and sorts them on the results, keeping the other servers after
+    /// them in their order. Call this after sorting, e.g. with a `LoadSorter`.
+    ///
+    /// Cutting the list to `n` servers before pinging, like the CLI does, throws the other servers
+    /// away. This keeps them as a fallback in case the pinged servers turn out to be bad. Returns
+    /// the results, e.g. to check which servers could not be pinged.
+    ///
+    /// The servers are pinged on `threads` threads, see
+    /// [PingSorter::ping_parallel](../sorters/struct.PingSorter.html#method.ping_parallel). With
+    /// 0 or 1 threads, they are pinged after one another and an error is returned if no server
+    /// could be pinged. The list is not changed on an error.
+    pub fn ping_keep(
+        &mut self,
+        n: usize,
+        config: &PingConfig,
+        threads: usize,
+    ) -> Result<PingSorter, Box<dyn std::error::Error>> {
+        let n = n.min(self.servers.len());
+        let top = Servers {
+            servers: self.servers[..n].to_vec(),
+            fetched_at: self.fetched_at,
+        };
+        let pings = if threads > 1 {
+            PingSorter::ping_parallel(&top, config, threads)
+        } else {
+            PingSorter::ping_with(&top, config)?
+        };
+        self.servers[..n].sort_by(|a, b| cmp_stable(a, b, &pings));
+        Ok(pings)
+    }
+
+    /// Sorts the servers by their domain, in alphabetical order.
+    pub fn sort_by_domain(&mut self) {
+        self.servers.sort_by(|x, y| x.domain.cmp(&y.domain));
+    }
+
+    /// Sorts the servers using the given comparison, putting the best server first. Unlike
+    /// [sort](#method.sort), the sort is stable: servers that compare equal keep their order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// // Sort by country, and by load within every country.
+    /// data.sort_by(|a, b| a.flag.cmp(&b.flag).then(a.load.cmp(&b.load)));
+    /// ```
+    pub fn sort_by<F: FnMut(&Server, &Server) -> std::cmp::Ordering>(&mut self, compare: F) {
+        self.servers.sort_by(compare);
+    }
+
+    /// Sorts the servers using a [Score](../sorters/trait.Score.html), putting the server with the
+    /// lowest score first. Servers with the same score keep their order.
+    pub fn sort_by_score(&mut self, score: &dyn Score) {
+        self.sort_by(|a, b| {
+            score
+                .score(a)
+                .partial_cmp(&score.score(b))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    /// Sorts the servers by a score combining their load and the given ping results, putting the
+    /// server with the lowest score first. Servers with the same score keep their order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// use nordselect::sorters::{PingSorter, ScoreWeights};
+    /// use std::collections::HashMap;
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// let mut results = HashMap::new();
+    /// results.insert("be1.nordvpn.com".to_string(), 15_000);
+    /// data.sort_score(&ScoreWeights::default(), &PingSorter::from(results));
+    /// ```
+    pub fn sort_score(&mut self, weights: &ScoreWeights, pings: &PingSorter) {
+        self.sort_by_score(&weights.with_pings(pings.clone()));
+    }
+
+    /// Adds a server at the end of the list, e.g. a server from another source.
+    pub fn push(&mut self, server: Server) {
+        self.servers.push(server);
+    }
+
+    /// Adds the given servers at the end of the list, in their order. Unlike
+    /// [merge](#method.merge), servers that are already in the list are added again.
+    pub fn extend<I: IntoIterator<Item = Server>>(&mut self, servers: I) {
+        self.servers.extend(servers);
+    }
+
+    /// Removes the server with the given domain, such as `nl372.nordvpn.com`, or short name, such as
+    /// `nl372`. Domains are compared case-insensitively. Returns whether a server was removed.
+    ///
+    /// If the list contains the server several times, every copy is removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// // Connecting to the best server failed, so try the next one.
+    /// let failed = data.perfect_server().unwrap();
+    /// assert!(data.remove_domain(&failed.domain));
+    /// assert_ne!(data.perfect_server(), Some(failed));
+    /// ```
+    pub fn remove_domain(&mut self, domain: &str) -> bool {
+        let domain = domain.trim();
+        let before = self.servers.len();
+        self.servers.retain(|server| !has_name(server, domain));
+        self.servers.len() != before
+    }
+
+    /// Moves the servers with a load of 0% to the end of the list, followed by the servers of which
+    /// the API did not provide the load at all. The order of the servers is kept otherwise. Call
+    /// this after sorting.
+    ///
+    /// A load of 0% usually means that the load is not measured, or even that the server is down,
+    /// so such a server should not be selected before the servers with a measured load.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// use nordselect::sorters::LoadSorter;
+    /// let mut data = Servers::dummy_data();
+    ///
+    /// data.sort(&LoadSorter);
+    /// data.deprioritize_zero_load();
+    /// if data.iter().any(|server| server.load > 0) {
+    ///     assert!(data.perfect_server().unwrap().load > 0);
+    /// }
+    /// ```
+    pub fn deprioritize_zero_load(&mut self) {
+        self.servers
+            .sort_by_key(|server| match (server.load_known, server.load) {
+                (false, _) => 2,
+                (true, 0) => 1,
+                _ => 0,
+            });
+    }
+
+    /// Removes all but the `max` best servers at the moment. Does nothing if there are less
+    /// servers.
+    ///
+    /// 'Best' servers are defined by the filters and sorters that should have been applied before
+    /// calling this function.
+    pub fn cut(&mut self, max: usize) {
+        self.servers.truncate(max);
+    }
+
+    /// Adds the servers of `other`, which is assumed to be fresher. A server in both lists is
+    /// replaced by the one of `other`, keeping its position. Servers only in `other` are appended
+    /// in their order. Servers are identified by their domain.
+    ///
+    /// `fetched_at` becomes the oldest moment of both lists, as the load of the servers only in
+    /// this list was not updated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nordselect::Servers;
+    /// let mut data = Servers::dummy_data();
+    /// let count = data.len();
+    ///
+    /// data.merge(Servers::dummy_data());
+    /// assert_eq!(data.len(), count);
+    /// ```
+    pub fn merge(&mut self, other: Servers) {
+        self.dedup_by_domain();
+        let mut positions: HashMap<String, usize> = HashMap::from_iter(
+            self.servers
+                .iter()
+                .enumerate()
+                .map(|(position, server)| (server.domain.clone(), position)),
+        );
+        for server in other.servers {
+            if let Some(&position) = positions.get(&server.domain) {
+                self.servers[position] = server;
+                continue;
+            }
+            positions.insert(server.domain.clone(), self.servers.len());
+            self.servers.push(server);
+        }
+        self.fetched_at = std::cmp::min(self.fetched_at, other.fetched_at);
+    }
+
+    /// Removes servers with the same domain as an earlier server, keeping the order of the others.
+    pub fn dedup_by_domain(&mut self) {
+        let mut seen = HashSet::new();
+        self.servers.retain(|server| seen.insert(server.domain.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asserts that both lists contain the same servers with the same fields, as servers are equal
+    /// if their domains are.
+    fn assert_same_servers(a: &[Server], b: &[Server]) {
+        assert_eq!(format!("{:?}", a), format!("{:?}", b));
+    }
+
+    /// Two servers in the format of the API. The second one has an invalid IP address.
+    const FIXTURE: &str = r#"[
+        {
+            "id": 1,
+            "ip_address": "185.229.191.44",
+            "search_keywords": ["P2P"],
+            "categories": [{"name": "Standard VPN servers"}, {"name": "P2P"}],
+            "name": "Netherlands #372",
+            "domain": "nl372.nordvpn.com",
+            "price": 0,
+            "flag": "NL",
+            "country": "Netherlands",
+            "location": {"lat": 52.366667, "long": 4.9},
+            "load": 12,
+            "features": {
+                "ikev2": true,
+                "openvpn_udp": true,
+                "openvpn_tcp": true,
+                "socks": false,
+                "proxy": false,
+                "pptp": false,
+                "l2tp": false,
+                "openvpn_xor_udp": false,
+                "openvpn_xor_tcp": false,
+                "proxy_cybersec": false,
+                "proxy_ssl": true,
+                "proxy_ssl_cybersec": true,
+                "wireguard_udp": true
+            }
+        },
+        {
+            "id": 2,
+            "ip_address": "not an ip",
+            "search_keywords": [],
+            "categories": [{"name": "Onion Over VPN"}],
+            "name": "Switzerland - Netherlands #1",
+            "domain": "ch-onion1.nordvpn.com",
+            "price": 0,
+            "flag": "CH",
+            "country": "Switzerland",
+            "location": {"lat": 47.366667, "long": 8.55},
+            "load": 3,
+            "features": {
+                "ikev2": false,
+                "openvpn_udp": true,
+                "openvpn_tcp": false,
+                "socks": false,
+                "proxy": false,
+                "pptp": false,
+                "l2tp": false,
+                "openvpn_xor_udp": false,
+                "openvpn_xor_tcp": false,
+                "proxy_cybersec": false,
+                "proxy_ssl": false,
+                "proxy_ssl_cybersec": false,
+                "wireguard_udp": false
+            }
+        }
+    ]"#;
+
+    #[test]
+    fn parse_fixture() {
+        let data = Servers::from_txt(FIXTURE).unwrap();
+
+        assert_eq!(data.servers.len(), 2);
+        assert_eq!(data.servers[0].domain, "nl372.nordvpn.com");
+        assert_eq!(data.servers[0].flag, "NL");
+        assert_eq!(data.servers[0].load, 12);
+        assert_eq!(
+            data.servers[0].categories,
+            vec![ServerCategory::Standard, ServerCategory::P2P]
+        );
+        assert_eq!(data.servers[1].categories, vec![ServerCategory::Tor]);
+    }
+
+    #[test]
+    fn parse_ip_address() {
+        let data = Servers::from_txt(FIXTURE).unwrap();
+
+        assert_eq!(
+            data.servers[0].ip(),
+            Some(IpAddr::from([185, 229, 191, 44]))
+        );
+        // An invalid IP address does not prevent the server from being parsed.
+        assert_eq!(data.servers[1].ip_address, "not an ip");
+        assert_eq!(data.servers[1].ip(), None);
+    }
+
+    #[test]
+    fn parse_missing_ip_address() {
+        let json = FIXTURE.replace("\"ip_address\": \"185.229.191.44\",", "");
+        let data = Servers::from_txt(&json).unwrap();
+
+        assert_eq!(data.servers[0].ip_address, "");
+        assert_eq!(data.servers[0].ip(), None);
+    }
+
+    #[test]
+    fn parse_location() {
+        let data = Servers::from_txt(FIXTURE).unwrap();
+
+        assert_eq!(
+            data.servers[0].location,
+            Some(Location {
+                lat: 52.366667,
+                long: 4.9
+            })
+        );
+
+        let json = FIXTURE.replace("\"location\": {\"lat\": 52.366667, \"long\": 4.9},", "");
+        let data = Servers::from_txt(&json).unwrap();
+
+        assert_eq!(data.servers[0].location, None);
+        assert!(data.servers[1].location.is_some());
+    }
+
+    #[test]
+    fn parse_names() {
+        let data = Servers::from_txt(FIXTURE).unwrap();
+
+        assert_eq!(data.servers[0].pretty_name, "Netherlands #372");
+        assert_eq!(data.servers[0].country, "Netherlands");
+
+        let json = FIXTURE
+            .replace("\"name\": \"Netherlands #372\",", "")
+            .replace("\"country\": \"Netherlands\",", "");
+        let data = Servers::from_txt(&json).unwrap();
+
+        assert_eq!(data.servers[0].pretty_name, "");
+        assert_eq!(data.servers[0].country, "");
+    }
+
+    #[test]
+    fn parse_search_keywords() {
+        let data = Servers::from_txt(FIXTURE).unwrap();
+
+        assert_eq!(data.servers[0].search_keywords, vec!["P2P"]);
+        assert!(data.servers[1].search_keywords.is_empty());
+
+        let json = FIXTURE.replace("\"search_keywords\": [\"P2P\"],", "");
+        let data = Servers::from_txt(&json).unwrap();
+
+        assert!(data.servers[0].search_keywords.is_empty());
+    }
+
+    /// Two servers in the format of the v1 API.
+    const FIXTURE_V1: &str = r#"[
+        {
+            "id": 929912,
+            "name": "Netherlands #372",
+            "station": "185.229.191.44",
+            "hostname": "nl372.nordvpn.com",
+            "load": 12,
+            "status": "online",
+            "locations": [
+                {
+                    "id": 133,
+                    "latitude": 52.366667,
+                    "longitude": 4.9,
+                    "country": {
+                        "id": 153,
+                        "name": "Netherlands",
+                        "code": "NL",
+                        "city": {"id": 6076868, "name": "Amsterdam", "dns_name": "amsterdam"}
+                    }
+                }
+            ],
+            "technologies": [
+                {"id": 1, "name": "IKEv2/IPSec", "identifier": "ikev2"},
+                {"id": 3, "name": "OpenVPN UDP", "identifier": "openvpn_udp"},
+                {"id": 5, "name": "OpenVPN TCP", "identifier": "openvpn_tcp"},
+                {"id": 35, "name": "Wireguard", "identifier": "wireguard_udp"},
+                {"id": 99, "name": "Future protocol", "identifier": "teleportation"}
+            ],
+            "groups": [
+                {"id": 11, "title": "Standard VPN servers", "type": {"identifier": "legacy_group_category"}},
+                {"id": 15, "title": "P2P", "type": {"identifier": "legacy_group_category"}},
+                {"id": 19, "title": "Europe", "type": {"identifier": "regions"}}
+            ]
+        },
+        {
+            "id": 12,
+            "name": "United States #1",
+            "station": "",
+            "hostname": "us1.nordvpn.com",
+            "load": 40,
+            "locations": [],
+            "technologies": [],
+            "groups": []
+        }
+    ]"#;
+
+    #[test]
+    fn parse_fixture_v1() {
+        let data = Servers::from_txt_v1(FIXTURE_V1).unwrap();
+        assert_eq!(data.servers.len(), 2);
+
+        let server = &data.servers[0];
+        assert_eq!(server.domain, "nl372.nordvpn.com");
+        assert_eq!(server.flag, "NL");
+        assert_eq!(server.load, 12);
+        assert_eq!(server.pretty_name, "Netherlands #372");
+        assert_eq!(server.country, "Netherlands");
+        assert_eq!(server.city, Some("Amsterdam".to_string()));
+        assert_eq!(server.ip(), Some(IpAddr::from([185, 229, 191, 44])));
+        assert_eq!(
+            server.location,
+            Some(Location {
+                lat: 52.366667,
+                long: 4.9
+            })
+        );
+        // Regions are not categories, but are kept as groups.
+        assert_eq!(
+            server.categories,
+            vec![ServerCategory::Standard, ServerCategory::P2P]
+        );
+        assert_eq!(server.groups, vec!["Europe".to_string()]);
+        assert_eq!(
+            server.features,
+            Features {
+                ikev2: true,
+                openvpn_udp: true,
+                openvpn_tcp: true,
+                wireguard_udp: true,
+                ..Features::default()
+            }
+        );
+
+        // Missing information is left empty.
+        let server = &data.servers[1];
+        assert_eq!(server.flag, "");
+        assert_eq!(server.city, None);
+        assert_eq!(server.location, None);
+        assert!(server.categories.is_empty());
+        assert!(server.groups.is_empty());
+        assert_eq!(server.features, Features::default());
+    }
+
+    #[test]
+    fn parse_same_server_from_both_apis() {
+        let legacy = Servers::from_txt(FIXTURE).unwrap();
+        let v1 = Servers::from_txt_v1(FIXTURE_V1).unwrap();
+
+        let (legacy, v1) = (&legacy.servers[0], &v1.servers[0]);
+        assert_eq!(legacy.domain, v1.domain);
+        assert_eq!(legacy.flag, v1.flag);
+        assert_eq!(legacy.load, v1.load);
+        assert_eq!(legacy.categories, v1.categories);
+        assert_eq!(legacy.ip_address, v1.ip_address);
+        assert_eq!(legacy.location, v1.location);
+    }
+
+    #[test]
+    fn recommendations_keep_order() {
+        use filters::CountryFilter;
+
+        assert_eq!(
+            recommendations_url(5, None),
+            "https://api.nordvpn.com/v1/servers/recommendations?limit=5"
+        );
+        assert_eq!(
+            recommendations_url(3, Some(153)),
+            "https://api.nordvpn.com/v1/servers/recommendations?limit=3&filters[country_id]=153"
+        );
+
+        // Recommendations use the format of the v1 API. NordVPN recommends us1 first here, which
+        // is kept instead of sorting by domain.
+        let mut recommended: Vec<serde_json::Value> = serde_json::from_str(FIXTURE_V1).unwrap();
+        recommended.reverse();
+        let txt = serde_json::to_string(&recommended).unwrap();
+        let mut data = Servers::from_txt_v1(&txt).unwrap();
+        assert_eq!(
+            domains_and_loads(&data),
+            vec![("us1.nordvpn.com", 40), ("nl372.nordvpn.com", 12)]
+        );
+
+        data.filter(&CountryFilter::from("nl"));
+        assert_eq!(data.servers.len(), 1);
+    }
+
+    #[test]
+    fn from_file_errors() {
+        let path = temp_path("nordselect_from_file_test");
+
+        std::fs::write(&path, FIXTURE).unwrap();
+        let data = Servers::from_file(&path).unwrap();
+        let expected = Servers::from_txt(FIXTURE).unwrap().by_domain();
+        assert_same_servers(&data.servers, &expected.servers);
+
+        std::fs::write(&path, "<html>Not JSON</html>").unwrap();
+        let error = Servers::from_file(&path).err().unwrap();
+        assert!(error.downcast_ref::<serde_json::Error>().is_some());
+
+        std::fs::remove_file(&path).unwrap();
+        let error = Servers::from_file(&path).err().unwrap();
+        assert_eq!(
+            error.downcast_ref::<std::io::Error>().unwrap().kind(),
+            std::io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn all_constructors_agree() {
+        let path = temp_path("nordselect_constructors_test");
+        std::fs::write(&path, FIXTURE).unwrap();
+
+        let from_str = Servers::from_str(FIXTURE).unwrap();
+        let from_reader = Servers::from_reader(FIXTURE.as_bytes()).unwrap();
+        let from_file = Servers::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(from_str.servers.len(), 2);
+        assert_eq!(from_str.servers, from_reader.servers);
+        assert_eq!(from_str.servers, from_file.servers);
+        // The servers are sorted by domain, regardless of the order in the file.
+        assert_eq!(from_str.servers[0].domain, "ch-onion1.nordvpn.com");
+        assert_eq!(from_str.servers[1].domain, "nl372.nordvpn.com");
+    }
+
+    #[test]
+    fn deserialize_raw_categories() {
+        let json = r#"[
+            "Standard VPN servers",
+            "P2P",
+            "Double VPN",
+            "Onion Over VPN",
+            "Obfuscated Servers",
+            "Dedicated IP",
+            "Anti DDoS"
+        ]"#;
+        let categories: Vec<ServerCategory> = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            categories,
+            vec![
+                ServerCategory::Standard,
+                ServerCategory::P2P,
+                ServerCategory::Double,
+                ServerCategory::Tor,
+                ServerCategory::Obfuscated,
+                ServerCategory::Dedicated,
+                ServerCategory::UnknownServer("Anti DDoS".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_category_keeps_name() {
+        let quantum = ServerCategory::from("Quantum VPN servers".to_string());
+        assert_eq!(
+            quantum,
+            ServerCategory::UnknownServer("Quantum VPN servers".to_string())
+        );
+        assert_ne!(quantum, ServerCategory::from("Anti DDoS".to_string()));
+    }
+
+    #[test]
+    fn streamed_parse_is_equivalent() {
+        let api_servers: Vec<ApiServer> = serde_json::from_str(FIXTURE).unwrap();
+        let expected: Vec<Server> = api_servers.into_iter().map(Server::from).collect();
+
+        assert_same_servers(&Servers::from_txt(FIXTURE).unwrap().servers, &expected);
+        assert_same_servers(
+            &Servers::from_json_reader(FIXTURE.as_bytes()).unwrap().servers,
+            &expected,
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn parse_async_stream() {
+        use futures::stream;
+
+        let chunks: Vec<Vec<u8>> = FIXTURE.as_bytes().chunks(64).map(Vec::from).collect();
+        let body = stream::iter_ok::<_, reqwest::Error>(chunks);
+        let data = Servers::from_stream(body).wait().unwrap();
+        assert_same_servers(&data.servers, &Servers::from_txt(FIXTURE).unwrap().servers);
+    }
+
+    #[test]
+    fn parse_features_missing_field() {
+        let features: Features = serde_json::from_str(r#"{"ikev2": true}"#).unwrap();
+        assert_eq!(
+            features,
+            Features {
+                ikev2: true,
+                ..Features::default()
+            }
+        );
+    }
+
+    #[test]
+    fn parse_features_extra_field() {
+        let features: Features =
+            serde_json::from_str(r#"{"ikev2": true, "quantum_tunnel": true, "teleport": false}"#)
+                .unwrap();
+        assert!(features.ikev2);
+        assert_eq!(features.get("ikev2"), Some(true));
+        assert_eq!(features.get("quantum_tunnel"), Some(true));
+        assert_eq!(features.get("teleport"), Some(false));
+        assert_eq!(features.get("time_travel"), None);
+    }
+
+    #[test]
+    fn parse_features_non_boolean_extra() {
+        let features: Features =
+            serde_json::from_str(r#"{"ikev2": true, "quantum_tunnel": "soon", "teleport": 1}"#)
+                .unwrap();
+        assert!(features.ikev2);
+        assert!(features.extra.is_empty());
+        assert_eq!(features.get("quantum_tunnel"), None);
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let mut data = Servers::from_txt(FIXTURE).unwrap();
+        data.servers[0].features.extra.insert("teleport".to_string(), true);
+        data.servers[1]
+            .categories
+            .push(ServerCategory::UnknownServer("Quantum VPN servers".to_string()));
+        data.servers[1].city = Some("Zurich".to_string());
+        data.fetched_at = SystemTime::now() - Duration::from_secs(3600);
+
+        let path = temp_path("nordselect_snapshot_round_trip");
+        data.save_snapshot(&path).unwrap();
+        let loaded = Servers::load_snapshot(&path).unwrap();
+        assert_same_servers(&loaded.servers, &data.servers);
+        assert_eq!(loaded.fetched_at, data.fetched_at);
+        assert!(loaded.is_stale(Duration::from_secs(1800)));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn snapshot_version_mismatch() {
+        let data = Servers::from_txt(FIXTURE).unwrap();
+        let path = temp_path("nordselect_snapshot_version");
+        data.save_snapshot(&path).unwrap();
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[0] = SNAPSHOT_VERSION + 1;
+        std::fs::write(&path, bytes).unwrap();
+
+        let error = Servers::load_snapshot(&path).err().unwrap();
+        assert!(error.to_string().contains("download the list again"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn parse_minimal_fields() {
+        let json = r#"[{
+            "ip_address": "185.229.191.44",
+            "categories": [{"name": "Standard VPN servers"}, {"name": "P2P"}],
+            "domain": "nl372.nordvpn.com",
+            "flag": "NL",
+            "load": 12,
+            "features": {
+                "ikev2": true,
+                "openvpn_udp": true,
+                "openvpn_tcp": true,
+                "socks": false,
+                "proxy": false,
+                "pptp": false,
+                "l2tp": false,
+                "openvpn_xor_udp": false,
+                "openvpn_xor_tcp": false,
+                "proxy_cybersec": false,
+                "proxy_ssl": true,
+                "proxy_ssl_cybersec": true,
+                "wireguard_udp": true
+            }
+        }]"#;
+        let minimal = &Servers::from_txt(json).unwrap().servers[0];
+        let full = &Servers::from_txt(FIXTURE).unwrap().servers[0];
+
+        assert_eq!(minimal.flag, full.flag);
+        assert_eq!(minimal.domain, full.domain);
+        assert_eq!(minimal.load, full.load);
+        assert_eq!(minimal.categories, full.categories);
+        assert_eq!(minimal.features, full.features);
+        assert_eq!(minimal.ip_address, full.ip_address);
+
+        // The other fields are left empty.
+        assert_eq!(minimal.location, None);
+        assert_eq!(minimal.pretty_name, "");
+    }
+
+    /// A response of the countries endpoint of the v1 API.
+    const COUNTRIES_V1: &str = r#"[
+        {"id": 21, "name": "Belgium", "code": "BE", "cities": []},
+        {"id": 153, "name": "Netherlands", "code": "NL", "cities": []},
+        {"id": 227, "name": "United Kingdom", "code": "GB", "cities": []}
+    ]"#;
+
+    #[test]
+    fn country_ids() {
+        assert_eq!(country_id(COUNTRIES_V1, "NL").unwrap(), Some(153));
+        assert_eq!(country_id(COUNTRIES_V1, "be").unwrap(), Some(21));
+        assert_eq!(country_id(COUNTRIES_V1, "GB").unwrap(), Some(227));
+        assert_eq!(country_id(COUNTRIES_V1, "UK").unwrap(), Some(227));
+        // A known country without servers.
+        assert_eq!(country_id(COUNTRIES_V1, "DE").unwrap(), None);
+        assert!(country_id("<html>", "NL").is_err());
+    }
+
+    #[test]
+    fn unknown_country_without_request() {
+        let error = Servers::from_api_for_country("XX").err().unwrap();
+        assert_eq!(error.to_string(), "unknown country: \"XX\"");
+    }
+
+    #[test]
+    fn fixture_covers_everything() {
+        let data = Servers::fixture();
+        assert_eq!(data.servers.len(), 30);
+        assert_eq!(data.flags().len(), 10);
+        for category in &[
+            ServerCategory::Standard,
+            ServerCategory::P2P,
+            ServerCategory::Obfuscated,
+            ServerCategory::Dedicated,
+            ServerCategory::Tor,
+            ServerCategory::Double,
+        ] {
+            assert!(data
+                .servers
+                .iter()
+                .any(|server| server.categories.contains(category)));
+        }
+        assert!(data.servers.iter().any(|server| server.features.openvpn_tcp));
+        assert!(data.servers.iter().any(|server| server.features.openvpn_udp));
+        assert!(data
+            .servers
+            .iter()
+            .any(|server| !server.features.openvpn_tcp && !server.features.openvpn_udp));
+    }
+
+    #[test]
+    fn wireguard_from_both_apis() {
+        use filters::ProtocolFilter;
+
+        // Older responses of the legacy API do not mention WireGuard.
+        let legacy = FIXTURE.replace(",\n                \"wireguard_udp\": true", "");
+        let mut data = Servers::from_txt(&legacy).unwrap();
+        assert!(data.servers.iter().all(|server| !server.features.wireguard_udp));
+        data.filter(&ProtocolFilter::from(Protocol::WireGuardUdp));
+        assert!(data.servers.is_empty());
+
+        let mut data = Servers::from_txt(FIXTURE).unwrap();
+        data.filter(&ProtocolFilter::from(Protocol::WireGuardUdp));
+        assert_eq!(data.servers.len(), 1);
+        assert_eq!(data.servers[0].domain, "nl372.nordvpn.com");
+
+        // The v1 API lists WireGuard among the technologies.
+        let mut data = Servers::from_txt_v1(FIXTURE_V1).unwrap();
+        data.filter(&ProtocolFilter::from(Protocol::WireGuardUdp));
+        assert_eq!(data.servers.len(), 1);
+        assert_eq!(data.servers[0].domain, "nl372.nordvpn.com");
+    }
+
+    #[test]
+    fn group_filter_v1() {
+        use filters::{AndFilter, CategoryFilter, Filter, GroupFilter};
+
+        let mut data = Servers::from_txt_v1(
+            r#"[{
+                "hostname": "nl1.nordvpn.com",
+                "load": 10,
+                "groups": [
+                    {"title": "P2P", "type": {"identifier": "legacy_group_category"}},
+                    {"title": "Europe", "type": {"identifier": "regions"}}
+                ]
+            }]"#,
+        )
+        .unwrap();
+        assert_eq!(data.servers[0].categories, vec![ServerCategory::P2P]);
+
+        data.filter(&AndFilter::from(vec![
+            Box::new(CategoryFilter::from(ServerCategory::P2P)) as Box<dyn Filter>,
+            Box::new(GroupFilter::from("Europe")),
+        ]));
+        assert_eq!(data.servers.len(), 1);
+    }
+
+    #[test]
+    fn keep_raw() {
+        let data = Servers::from_json_reader_with(FIXTURE.as_bytes(), true).unwrap();
+        let raw = data.servers[0].raw.as_ref().unwrap();
+        // Fields this library does not use are kept.
+        assert_eq!(raw["id"], 1);
+        assert_eq!(raw["price"], 0);
+        assert_eq!(raw["domain"], "nl372.nordvpn.com");
+        // The servers are parsed the same way.
+        let mut parsed = data.servers.clone();
+        for server in &mut parsed {
+            server.raw = None;
+        }
+        assert_same_servers(&parsed, &Servers::from_txt(FIXTURE).unwrap().servers);
+
+        let data = Servers::from_json_reader_with(FIXTURE.as_bytes(), false).unwrap();
+        assert!(data.servers.iter().all(|server| server.raw.is_none()));
+        assert!(Servers::from_txt(FIXTURE)
+            .unwrap()
+            .servers
+            .iter()
+            .all(|server| server.raw.is_none()));
+    }
+
+    #[test]
+    fn age_and_staleness() {
+        let mut data = Servers::from_txt(FIXTURE).unwrap();
+        assert!(data.age() < Duration::from_secs(60));
+        assert!(!data.is_stale(Duration::from_secs(60)));
+
+        data.fetched_at = SystemTime::now() - Duration::from_secs(600);
+        assert!(data.age() >= Duration::from_secs(600));
+        assert!(data.is_stale(Duration::from_secs(300)));
+        assert!(!data.is_stale(Duration::from_secs(3600)));
+
+        // A moment in the future counts as now.
+        data.fetched_at = SystemTime::now() + Duration::from_secs(600);
+        assert_eq!(data.age(), Duration::from_secs(0));
+        assert!(!data.is_stale(Duration::from_secs(0)));
+    }
+
+    #[test]
+    fn from_file_fetched_at() {
+        let path = temp_path("nordselect_from_file_fetched_at");
+        std::fs::write(&path, FIXTURE).unwrap();
+        let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+        assert_eq!(Servers::from_file(&path).unwrap().fetched_at, modified);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn perfect_servers() {
+        let data = Servers::from(
+            ["nl1", "nl2", "nl3"]
+                .iter()
+                .map(|name| {
+                    Server::builder()
+                        .domain(&format!("{}.nordvpn.com", name))
+                        .build()
+                })
+                .collect::<Vec<_>>(),
+        );
+        let names = |n| {
+            data.perfect_servers(n)
+                .iter()
+                .map(|server| server.name().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(2), vec!["nl1", "nl2"]);
+        assert_eq!(names(3), vec!["nl1", "nl2", "nl3"]);
+        assert_eq!(names(5), vec!["nl1", "nl2", "nl3"]);
+        assert!(names(0).is_empty());
+        // The list is left untouched.
+        assert_eq!(data.servers.len(), 3);
+        assert_eq!(data.perfect_server().unwrap().domain, "nl1.nordvpn.com");
+
+        let empty = Servers::from(Vec::new());
+        assert!(empty.perfect_servers(3).is_empty());
+        assert_eq!(empty.perfect_server(), None);
+    }
+
+    #[test]
+    fn iterate_servers() {
+        use sorters::LoadSorter;
+
+        let mut data = Servers::from(vec![
+            Server::builder().domain("nl1.nordvpn.com").load(30).build(),
+            Server::builder().domain("nl2.nordvpn.com").load(10).build(),
+            Server::builder().domain("nl3.nordvpn.com").load(20).build(),
+        ]);
+        data.sort(&LoadSorter);
+
+        let total: u32 = data.iter().map(|server| u32::from(server.load)).sum();
+        assert_eq!(total, 60);
+
+        // The iterators follow the sorted order.
+        let loads: Vec<u8> = (&data).into_iter().map(|server| server.load).collect();
+        assert_eq!(loads, vec![10, 20, 30]);
+        let domains: Vec<String> = data.into_iter().map(|server| server.domain).collect();
+        assert_eq!(
+            domains,
+            vec!["nl2.nordvpn.com", "nl3.nordvpn.com", "nl1.nordvpn.com"]
+        );
+    }
+
+    #[test]
+    fn servers_slice() {
+        use sorters::LoadSorter;
+
+        let mut data = Servers::from_txt(FIXTURE).unwrap();
+        data.sort(&LoadSorter);
+        let servers = data.servers();
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0].domain, "ch-onion1.nordvpn.com");
+        assert_eq!(
+            servers.binary_search_by_key(&12, |server| server.load).ok(),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn len_and_is_empty() {
+        let mut data = Servers::from_txt(FIXTURE).unwrap();
+        assert_eq!(data.len(), 2);
+        assert!(!data.is_empty());
+
+        data.filter(&CategoryFilter::from(ServerCategory::Tor));
+        assert_eq!(data.len(), 1);
+
+        data.filter(&CategoryFilter::from(ServerCategory::P2P));
+        assert_eq!(data.len(), 0);
+        assert!(data.is_empty());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut data = Servers::from_txt(FIXTURE).unwrap();
+        data.servers[0].features.extra.insert("teleport".to_string(), true);
+        data.servers[1]
+            .categories
+            .push(ServerCategory::UnknownServer("Quantum VPN servers".to_string()));
+
+        let json = data.to_json().unwrap();
+        // Categories and features use the names of the API.
+        assert!(json.contains(r#""categories":["Standard VPN servers","P2P"]"#));
+        assert!(json.contains(r#""Onion Over VPN""#));
+        assert!(json.contains(r#""Quantum VPN servers""#));
+        assert!(json.contains(r#""wireguard_udp":true"#));
+        assert!(json.contains(r#""teleport":true"#));
+        assert!(json.contains(r#""city":null"#));
+
+        let servers: Vec<Server> = serde_json::from_str(&json).unwrap();
+        assert_same_servers(&servers, &data.servers);
+    }
+
+    #[test]
+    fn display_server() {
+        let data = Servers::from_txt(FIXTURE).unwrap();
+        assert_eq!(data.servers[0].to_string(), "nl372 (NL, load 12%, standard/p2p)");
+        assert_eq!(data.servers[1].to_string(), "ch-onion1 (CH, load 3%, tor)");
+
+        let server = Server::builder()
+            .domain("vpn.example.com")
+            .flag("BE")
+            .load(100)
+            .build();
+        assert_eq!(server.to_string(), "vpn.example.com (BE, load 100%)");
+    }
+
+    #[test]
+    fn display_category() {
+        use filters::category_from_str;
+
+        let categories = vec![
+            ServerCategory::Standard,
+            ServerCategory::P2P,
+            ServerCategory::Double,
+            ServerCategory::Tor,
+            ServerCategory::Obfuscated,
+            ServerCategory::Dedicated,
+            ServerCategory::UnknownServer("Quantum VPN servers".to_string()),
+        ];
+        let names: Vec<String> = categories.iter().map(|category| category.to_string()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "standard",
+                "p2p",
+                "double",
+                "tor",
+                "obfuscated",
+                "dedicated",
+                "Quantum VPN servers"
+            ]
+        );
+        // The short names are accepted by the CLI.
+        for category in &categories[..6] {
+            assert_eq!(
+                category_from_str(&category.to_string()).as_ref(),
+                Some(category)
+            );
+        }
+    }
+
+    /// Splits a CSV line into its cells, unquoting them.
+    fn parse_csv_line(line: &str) -> Vec<String> {
+        let mut cells = vec![String::new()];
+        let mut quoted = false;
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    cells.last_mut().unwrap().push('"');
+                }
+                '"' => quoted = !quoted,
+                ',' if !quoted => cells.push(String::new()),
+                c => cells.last_mut().unwrap().push(c),
+            }
+        }
+        cells
+    }
+
+    #[test]
+    fn csv_export() {
+        let mut data = Servers::from_txt(FIXTURE).unwrap();
+        data.servers[1].domain = "weird, \"quoted\".nordvpn.com".to_string();
+
+        let mut csv = Vec::new();
+        data.to_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let rows: Vec<Vec<String>> = csv.lines().map(parse_csv_line).collect();
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], CSV_COLUMNS.iter().map(|c| c.to_string()).collect::<Vec<_>>());
+        assert!(rows.iter().all(|row| row.len() == CSV_COLUMNS.len()));
+        assert_eq!(rows[1][0], "nl372.nordvpn.com");
+        assert_eq!(rows[1][1], "NL");
+        assert_eq!(rows[1][2], "12");
+        assert_eq!(rows[1][3], "standard;p2p");
+        assert_eq!(rows[1][7], "true");
+        assert_eq!(rows[2][0], "weird, \"quoted\".nordvpn.com");
+        assert_eq!(rows[2][3], "tor");
+        assert_eq!(rows[2][6], "false");
+    }
+
+    #[test]
+    fn json_lines_export() {
+        let data = Servers::from_txt(FIXTURE).unwrap();
+
+        let mut output = Vec::new();
+        data.to_json_lines(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.ends_with("}\n"));
+
+        let servers: Vec<Server> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_same_servers(&servers, &data.servers);
+
+        let mut output = Vec::new();
+        Servers::from(Vec::new()).to_json_lines(&mut output).unwrap();
+        assert!(output.is_empty());
+    }
+
+    /// Returns a server with the given domain and load.
+    fn loaded(domain: &str, load: u8) -> Server {
+        Server::builder().domain(domain).load(load).build()
+    }
+
+    /// Returns a server with the given domain and country.
+    fn flagged(domain: &str, flag: &str) -> Server {
+        Server::builder().domain(domain).flag(flag).build()
+    }
+
+    /// Returns the short names of the servers, in order.
+    fn names(data: &Servers) -> Vec<&str> {
+        data.iter().map(|server| server.name().unwrap()).collect()
+    }
+
+    /// Returns a path in the temporary directory with the given name, made unique to this process
+    /// and call.
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        std::env::temp_dir().join(format!(
+            "{}_{}_{}",
+            name,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ))
+    }
+
+    /// Returns the domains and loads of the servers, in order.
+    fn domains_and_loads(data: &Servers) -> Vec<(&str, u8)> {
+        data.iter()
+            .map(|server| (server.domain.as_str(), server.load))
+            .collect()
+    }
+
+    #[test]
+    fn merge_overlapping() {
+        let mut data = Servers::from(vec![loaded("a", 10), loaded("b", 20), loaded("c", 30)]);
+        let mut fresher = Servers::from(vec![loaded("c", 5), loaded("d", 40), loaded("a", 50)]);
+        fresher.fetched_at = data.fetched_at + Duration::from_secs(60);
+
+        data.merge(fresher);
+        assert_eq!(
+            domains_and_loads(&data),
+            vec![("a", 50), ("b", 20), ("c", 5), ("d", 40)]
+        );
+        // Server b was not updated.
+        assert!(data.age() < Duration::from_secs(60));
+    }
+
+    #[test]
+    fn merge_disjoint() {
+        let mut data = Servers::from(vec![loaded("a", 10)]);
+        data.merge(Servers::from(vec![loaded("b", 20), loaded("c", 30)]));
+        assert_eq!(
+            domains_and_loads(&data),
+            vec![("a", 10), ("b", 20), ("c", 30)]
+        );
+
+        let mut data = Servers::from(Vec::new());
+        data.merge(Servers::from(vec![loaded("a", 10), loaded("a", 20)]));
+        assert_eq!(domains_and_loads(&data), vec![("a", 20)]);
+    }
+
+    #[test]
+    fn dedup_by_domain() {
+        let mut data = Servers::from(vec![
+            loaded("a", 10),
+            loaded("b", 20),
+            loaded("a", 30),
+            loaded("c", 40),
+            loaded("b", 50),
+        ]);
+        data.dedup_by_domain();
+        assert_eq!(
+            domains_and_loads(&data),
+            vec![("a", 10), ("b", 20), ("c", 40)]
+        );
+    }
+
+    #[test]
+    fn sort_by_custom_comparison() {
+        let mut data = Servers::from(vec![
+            Server::builder().domain("nl20.nordvpn.com").flag("NL").load(5).build(),
+            Server::builder().domain("be7.nordvpn.com").flag("BE").load(30).build(),
+            Server::builder().domain("nl3.nordvpn.com").flag("NL").load(5).build(),
+            Server::builder().domain("be1.nordvpn.com").flag("BE").load(10).build(),
+        ]);
+
+        // Prefer lower server numbers.
+        data.sort_by(|a, b| a.number().cmp(&b.number()));
+        assert_eq!(
+            data.iter().map(|server| server.number()).collect::<Vec<_>>(),
+            vec![Some(1), Some(3), Some(7), Some(20)]
+        );
+
+        // Sort by country, then by load. Servers with the same load keep their order.
+        data.sort_by(|a, b| a.flag.cmp(&b.flag).then(a.load.cmp(&b.load)));
+        assert_eq!(
+            data.iter().map(|server| server.name().unwrap()).collect::<Vec<_>>(),
+            vec!["be1", "be7", "nl3", "nl20"]
+        );
+    }
+
+    /// Returns pseudo-random servers with random ping results, for property tests.
+    fn random_servers(seed: u64, count: usize) -> (Servers, PingSorter) {
+        let mut state = seed;
+        let mut next = move |max: u64| {
+            state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            (state >> 33) % max
+        };
+        let mut results = HashMap::new();
+        let mut servers = Vec::new();
+        for number in 0..count {
+            let domain = format!("nl{}.nordvpn.com", number);
+            // Some servers were not pinged.
+            if next(4) != 0 {
+                results.insert(domain.clone(), next(200_000) as usize);
+            }
+            servers.push(Server::builder().domain(&domain).load(next(101) as u8).build());
+        }
+        (Servers::from(servers), PingSorter::from(results))
+    }
+
+    #[test]
+    fn sort_score_without_ping_is_load() {
+        let weights = ScoreWeights {
+            ping: 0.0,
+            ..ScoreWeights::default()
+        };
+        for seed in 0..20 {
+            let (mut scored, pings) = random_servers(seed, 30);
+            let mut by_load = Servers::from(scored.servers.clone());
+            scored.sort_score(&weights, &pings);
+            by_load.sort_by(|a, b| a.load.cmp(&b.load));
+            assert_eq!(scored.servers, by_load.servers);
+        }
+    }
+
+    #[test]
+    fn sort_score_without_load_is_ping() {
+        let weights = ScoreWeights {
+            load: 0.0,
+            ..ScoreWeights::default()
+        };
+        for seed in 0..20 {
+            let (mut scored, pings) = random_servers(seed, 30);
+            let mut by_ping = Servers::from(scored.servers.clone());
+            scored.sort_score(&weights, &pings);
+            by_ping.sort_by(|a, b| pings.sort(a, b));
+            let latencies = |data: &Servers| {
+                data.iter()
+                    .map(|server| pings.latency(&server.domain))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(latencies(&scored), latencies(&by_ping));
+        }
+    }
+
+    #[test]
+    fn sort_score_combines() {
+        let mut data = Servers::from(vec![
+            Server::builder().domain("far.nordvpn.com").load(10).build(),
+            Server::builder().domain("near.nordvpn.com").load(20).build(),
+            Server::builder().domain("unknown.nordvpn.com").load(0).build(),
+        ]);
+        let mut results = HashMap::new();
+        results.insert("far.nordvpn.com".to_string(), 80_000);
+        results.insert("near.nordvpn.com".to_string(), 20_000);
+
+        data.sort_score(&ScoreWeights::default(), &PingSorter::from(results));
+        assert_eq!(names(&data), vec!["near", "far", "unknown"]);
+    }
+
+    #[test]
+    fn pick_random_top() {
+        let data = Servers::from(
+            (1..6)
+                .map(|number| {
+                    Server::builder()
+                        .domain(&format!("nl{}.nordvpn.com", number))
+                        .build()
+                })
+                .collect::<Vec<_>>(),
+        );
+        let pick = |n, seed| {
+            data.pick_random_top_seeded(n, seed)
+                .and_then(|server| server.number())
+        };
+
+        // The same seed picks the same server.
+        assert_eq!(pick(3, 42), Some(2));
+        assert_eq!(pick(3, 42), pick(3, 42));
+        assert_eq!(pick(1, 42), Some(1));
+        // Only the best servers are picked.
+        assert!((0..100).all(|seed| pick(3, seed).unwrap() <= 3));
+        // All servers are picked if there are less than n.
+        let picked: HashSet<u32> = (0..100).map(|seed| pick(10, seed).unwrap()).collect();
+        assert_eq!(picked.len(), 5);
+        assert_eq!(pick(0, 42), None);
+
+        assert_eq!(data.len(), 5);
+        assert!(data.pick_random_top(3).unwrap().number().unwrap() <= 3);
+        assert_eq!(Servers::from(Vec::new()).pick_random_top(3), None);
+    }
+
+    #[test]
+    fn group_by_country() {
+        let data = Servers::from(vec![
+            flagged("nl1.nordvpn.com", "NL"),
+            flagged("be1.nordvpn.com", "BE"),
+            flagged("nl2.nordvpn.com", "nl"),
+            flagged("de1.nordvpn.com", "DE"),
+            flagged("nl3.nordvpn.com", "NL"),
+        ]);
+
+        let counts = data.country_counts();
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts["NL"], 3);
+        assert_eq!(counts["BE"], 1);
+        assert_eq!(counts["DE"], 1);
+        assert_eq!(counts.values().sum::<usize>(), data.len());
+
+        let countries = data.by_country();
+        assert_eq!(countries.values().map(Vec::len).sum::<usize>(), data.len());
+        let dutch: Vec<&str> = countries["NL"]
+            .iter()
+            .map(|server| server.domain.as_str())
+            .collect();
+        assert_eq!(
+            dutch,
+            vec!["nl1.nordvpn.com", "nl2.nordvpn.com", "nl3.nordvpn.com"]
+        );
+        assert!(!countries.contains_key("nl"));
+    }
+
+    #[test]
+    fn categories_present() {
+        use filters::CountryFilter;
+
+        let mut data = Servers::from_txt(FIXTURE).unwrap();
+        let expected: HashSet<ServerCategory> = vec![
+            ServerCategory::Standard,
+            ServerCategory::P2P,
+            ServerCategory::Tor,
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(data.categories(), expected);
+
+        data.filter(&CountryFilter::from("CH"));
+        let expected: HashSet<ServerCategory> = vec![ServerCategory::Tor].into_iter().collect();
+        assert_eq!(data.categories(), expected);
+    }
+
+    #[test]
+    fn filtered_keeps_original() {
+        use filters::CountryFilter;
+
+        let data = Servers::fixture();
+        let original = data.servers.clone();
+
+        let swedish = data.filtered(&CountryFilter::from("SE"));
+        let belgian = data.filtered(&CountryFilter::from("BE"));
+        assert_eq!(swedish.len(), 2);
+        assert!(swedish.iter().all(|server| server.flag == "SE"));
+        assert_eq!(belgian.len(), 3);
+        assert!(belgian.iter().all(|server| server.flag == "BE"));
+        assert_eq!(belgian.fetched_at, data.fetched_at);
+
+        assert_eq!(data.servers, original);
+        let copy = data.clone();
+        assert_eq!(copy.servers, data.servers);
+        assert_eq!(copy.fetched_at, data.fetched_at);
+    }
+
+    #[test]
+    fn shuffle() {
+        let numbered = || {
+            Servers::from(
+                (1..7)
+                    .map(|number| {
+                        Server::builder()
+                            .domain(&format!("nl{}.nordvpn.com", number))
+                            .build()
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        };
+        let numbers = |data: &Servers| {
+            data.iter()
+                .map(|server| server.number().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        // The same seed shuffles the same way.
+        let mut data = numbered();
+        data.shuffle_seeded(42);
+        assert_eq!(numbers(&data), vec![5, 4, 1, 3, 6, 2]);
+        let mut data = numbered();
+        data.shuffle_seeded(7);
+        assert_eq!(numbers(&data), vec![2, 6, 1, 3, 5, 4]);
+
+        // Every server is kept.
+        let mut data = numbered();
+        data.shuffle();
+        let mut shuffled = numbers(&data);
+        shuffled.sort();
+        assert_eq!(shuffled, vec![1, 2, 3, 4, 5, 6]);
+
+        let mut empty = Servers::from(Vec::new());
+        empty.shuffle();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn load_stats_by_country() {
+        let server = |flag: &str, load: u8| Server::builder().flag(flag).load(load).build();
+        let data = Servers::from(vec![
+            server("NL", 10),
+            server("BE", 50),
+            server("NL", 255),
+            server("nl", 200),
+            server("NL", 255),
+        ]);
+
+        let stats = data.load_stats_by_country();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(
+            stats["NL"],
+            LoadStats {
+                count: 4,
+                min: 10,
+                max: 255,
+                // (10 + 255 + 200 + 255) / 4, which does not fit in a u8.
+                mean: 180.0,
+            }
+        );
+        assert_eq!(
+            stats["BE"],
+            LoadStats {
+                count: 1,
+                min: 50,
+                max: 50,
+                mean: 50.0,
+            }
+        );
+        assert!(Servers::from(Vec::new()).load_stats_by_country().is_empty());
+    }
+
+    #[test]
+    fn remove_domain() {
+        let mut data = Servers::from(vec![
+            loaded("nl1.nordvpn.com", 0),
+            loaded("nl2.nordvpn.com", 0),
+            loaded("be1.nordvpn.com", 0),
+            loaded("nl2.nordvpn.com", 0),
+            loaded("vpn.example.com", 0),
+        ]);
+        let domains = |data: &Servers| {
+            data.iter()
+                .map(|server| server.domain.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(data.remove_domain("NL1.nordvpn.com"));
+        assert_eq!(data.len(), 4);
+        // Every copy of the server is removed.
+        assert!(data.remove_domain("Nl2"));
+        assert_eq!(domains(&data), vec!["be1.nordvpn.com", "vpn.example.com"]);
+
+        assert!(!data.remove_domain("nl2"));
+        assert!(!data.remove_domain("be1.nordvpn"));
+        assert!(!data.remove_domain("vpn"));
+        assert!(data.remove_domain("vpn.example.com"));
+        assert_eq!(domains(&data), vec!["be1.nordvpn.com"]);
+    }
+
+    #[test]
+    fn build_servers_by_hand() {
+        use filters::{CountryFilter, ProtocolFilter};
+
+        let server = |domain: &str, flag: &str, tcp: bool| {
+            Server::builder()
+                .domain(domain)
+                .flag(flag)
+                .load(10)
+                .category(ServerCategory::Standard)
+                .features(Features {
+                    openvpn_tcp: tcp,
+                    ..Features::default()
+                })
+                .build()
+        };
+        let mut data: Servers = vec![server("nl1.nordvpn.com", "NL", true)]
+            .into_iter()
+            .collect();
+        data.push(server("nl2.nordvpn.com", "NL", false));
+        data.extend(vec![
+            server("be1.nordvpn.com", "BE", true),
+            server("nl3.nordvpn.com", "NL", true),
+        ]);
+        assert_eq!(data.len(), 4);
+
+        data.filter(&CountryFilter::from("NL"));
+        data.filter(&ProtocolFilter::from(Protocol::Tcp));
+        assert_eq!(names(&data), vec!["nl1", "nl3"]);
+    }
+
+    #[test]
+    fn sort_stable_ties_on_name() {
+        use sorters::LoadSorter;
+
+        let mut data = Servers::from(vec![
+            loaded("nl3.nordvpn.com", 10),
+            loaded("be1.nordvpn.com", 20),
+            loaded("nl1.nordvpn.com", 10),
+            loaded("de1.nordvpn.com", 10),
+        ]);
+
+        data.sort_stable(&LoadSorter);
+        assert_eq!(names(&data), vec!["de1", "nl1", "nl3", "be1"]);
+
+        // Equal pings are ordered by domain as well.
+        let mut results = HashMap::new();
+        for name in &["nl3", "be1", "nl1", "de1"] {
+            results.insert(format!("{}.nordvpn.com", name), 5_000);
+        }
+        data.sort_stable(&PingSorter::from(results));
+        assert_eq!(names(&data), vec!["be1", "de1", "nl1", "nl3"]);
+
+        data.shuffle_seeded(3);
+        data.sort_by_domain();
+        assert_eq!(names(&data), vec!["be1", "de1", "nl1", "nl3"]);
+    }
+
+    #[test]
+    fn best_ties_on_ping_then_name() {
+        let mut data = Servers::from(vec![
+            loaded("us-ca1.nordvpn.com", 7),
+            loaded("nl2.nordvpn.com", 7),
+            loaded("us.nordvpn.com", 7),
+            loaded("be1.nordvpn.com", 30),
+        ]);
+        let name = |server: Option<&Server>| server.and_then(Server::name).map(str::to_string);
+
+        // Without pings, the short names decide: "us" comes before "us-ca1".
+        assert_eq!(name(data.best(None)), Some("nl2".to_string()));
+        data.servers.retain(|server| server.domain != "nl2.nordvpn.com");
+        assert_eq!(name(data.best(None)), Some("us".to_string()));
+
+        let mut results = HashMap::new();
+        results.insert("us-ca1.nordvpn.com".to_string(), 20_000);
+        results.insert("us.nordvpn.com".to_string(), 30_000);
+        results.insert("be1.nordvpn.com".to_string(), 1_000);
+        let pings = PingSorter::from(results);
+        data.push(loaded("nl2.nordvpn.com", 7));
+
+        // The lowest ping wins among the least loaded servers; nl2 was not pinged.
+        assert_eq!(name(data.best(Some(&pings))), Some("us-ca1".to_string()));
+
+        data.sort_best(Some(&pings));
+        data.cut(1);
+        assert_eq!(name(data.perfect_server().as_ref()), Some("us-ca1".to_string()));
+    }
+
+    #[test]
+    fn supports_protocols() {
+        let protocols = vec![
+            (Protocol::Udp, "openvpn_udp"),
+            (Protocol::Tcp, "openvpn_tcp"),
+            (Protocol::Pptp, "pptp"),
+            (Protocol::L2tp, "l2tp"),
+            (Protocol::OpenVPNXUdp, "openvpn_xor_udp"),
+            (Protocol::OpenVPNXTcp, "openvpn_xor_tcp"),
+            (Protocol::Socks, "socks"),
+            (Protocol::CyberSecProxy, "proxy_cybersec"),
+            (Protocol::SslProxy, "proxy_ssl"),
+            (Protocol::CyberSecSslProxy, "proxy_ssl_cybersec"),
+            (Protocol::Proxy, "proxy"),
+            (Protocol::WireGuardUdp, "wireguard_udp"),
+            (Protocol::Ikev2, "ikev2"),
+        ];
+
+        for &(protocol, feature) in &protocols {
+            let technologies = [ApiV1Technology {
+                identifier: feature.to_string(),
+            }];
+            let server = Server::builder()
+                .features(Features::from(&technologies[..]))
+                .build();
+            for &(other, _) in &protocols {
+                assert_eq!(
+                    server.supports(other),
+                    other == protocol,
+                    "{} should only support {:?}, checked {:?}",
+                    feature,
+                    protocol,
+                    other
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn has_category() {
+        let server = Server::builder()
+            .category(ServerCategory::Standard)
+            .category(ServerCategory::P2P)
+            .build();
+
+        assert!(server.has_category(&ServerCategory::Standard));
+        assert!(server.has_category(&ServerCategory::P2P));
+        assert!(!server.has_category(&ServerCategory::Double));
+        assert!(!server.has_category(&ServerCategory::UnknownServer("Other".to_string())));
+    }
+
+    #[test]
+    fn best_per_country() {
+        use sorters::LoadSorter;
+
+        let server = |domain: &str, flag: &str, load: u8| {
+            Server::builder().domain(domain).flag(flag).load(load).build()
+        };
+        let mut data = Servers::from(vec![
+            server("nl1.nordvpn.com", "NL", 40),
+            server("be1.nordvpn.com", "BE", 12),
+            server("nl2.nordvpn.com", "NL", 8),
+            server("be2.nordvpn.com", "BE", 30),
+            server("nl3.nordvpn.com", "nl", 25),
+        ]);
+        data.sort_stable(&LoadSorter);
+
+        let best = data.best_per_country();
+        assert_eq!(best.len(), 2);
+        assert_eq!(best["NL"].domain, "nl2.nordvpn.com");
+        assert_eq!(best["BE"].domain, "be1.nordvpn.com");
+        assert_eq!(data.len(), 5);
+    }
+
+    #[test]
+    fn partition() {
+        use filters::CategoryFilter;
+
+        let server = |domain: &str, category: ServerCategory| {
+            Server::builder().domain(domain).category(category).build()
+        };
+        let data = Servers::from(vec![
+            server("nl1.nordvpn.com", ServerCategory::P2P),
+            server("nl2.nordvpn.com", ServerCategory::Standard),
+            server("be1.nordvpn.com", ServerCategory::P2P),
+            server("be2.nordvpn.com", ServerCategory::Standard),
+            server("de1.nordvpn.com", ServerCategory::P2P),
+        ]);
+
+        let (p2p, others) = data.partition(&CategoryFilter::from(ServerCategory::P2P));
+        assert_eq!(names(&p2p), vec!["nl1", "be1", "de1"]);
+        assert_eq!(names(&others), vec!["nl2", "be2"]);
+        assert!(p2p.iter().all(|server| !others.iter().any(|other| other == server)));
+        assert_eq!(data.len(), 5);
+    }
+
+    #[test]
+    fn find() {
+        let data = Servers::from(vec![
+            loaded("de750.nordvpn.com", 12),
+            loaded("de75.nordvpn.com", 30),
+            loaded("nl-uk1.nordvpn.com", 40),
+        ]);
+
+        assert_eq!(data.find("de750").map(|server| server.load), Some(12));
+        assert_eq!(data.find("DE750.nordvpn.com").map(|server| server.load), Some(12));
+        assert_eq!(data.find(" de75 ").map(|server| server.load), Some(30));
+        assert_eq!(data.find("nl-UK1").map(|server| server.load), Some(40));
+        assert_eq!(data.find("de7"), None);
+        assert_eq!(data.find("de750.nordvpn"), None);
+    }
+
+    #[test]
+    fn server_equality_on_domain() {
+        let old = Server::builder().domain("nl1.nordvpn.com").load(10).build();
+        let new = Server::builder().domain("nl1.nordvpn.com").load(60).build();
+        let other = Server::builder().domain("nl2.nordvpn.com").load(10).build();
+
+        assert_eq!(old, new);
+        assert_ne!(old, other);
+        let set: HashSet<Server> = vec![old, new, other].into_iter().collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn diff() {
+        let old = Servers::from(vec![
+            loaded("nl1.nordvpn.com", 10),
+            loaded("nl2.nordvpn.com", 20),
+            loaded("be1.nordvpn.com", 30),
+        ]);
+        let new = Servers::from(vec![
+            loaded("be1.nordvpn.com", 5),
+            loaded("de1.nordvpn.com", 15),
+            loaded("nl1.nordvpn.com", 25),
+        ]);
+        let domains = |servers: Vec<&Server>| {
+            servers
+                .into_iter()
+                .map(|server| server.domain.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let (added, removed) = new.diff(&old);
+        assert_eq!(domains(added), vec!["de1.nordvpn.com"]);
+        assert_eq!(domains(removed), vec!["nl2.nordvpn.com"]);
+
+        let same = new.clone();
+        let (added, removed) = new.diff(&same);
+        assert!(added.is_empty() && removed.is_empty());
+    }
+
+    #[test]
+    fn sample_per_country() {
+        let data = Servers::from(vec![
+            flagged("us1.nordvpn.com", "US"),
+            flagged("us2.nordvpn.com", "US"),
+            flagged("is1.nordvpn.com", "IS"),
+            flagged("us3.nordvpn.com", "US"),
+            flagged("us4.nordvpn.com", "us"),
+        ]);
+
+        assert_eq!(names(&data.sample_per_country(2)), vec!["us1", "us2", "is1"]);
+        assert_eq!(names(&data.sample_per_country(1)), vec!["us1", "is1"]);
+        assert_eq!(data.sample_per_country(10).len(), 5);
+        assert!(data.sample_per_country(0).is_empty());
+    }
+
+    #[test]
+    fn deprioritize_zero_load() {
+        use sorters::LoadSorter;
+
+        let mut data = Servers::from(vec![
+            loaded("nl1.nordvpn.com", 3),
+            Server::builder().domain("nl4.nordvpn.com").unknown_load().build(),
+            loaded("nl2.nordvpn.com", 0),
+            loaded("nl3.nordvpn.com", 8),
+        ]);
+
+        data.sort_stable(&LoadSorter);
+        assert_eq!(data.perfect_server().unwrap().load, 0);
+
+        // The unknown load is worse than a reported load of 0%.
+        data.deprioritize_zero_load();
+        assert_eq!(names(&data), vec!["nl1", "nl3", "nl2", "nl4"]);
+    }
+
+    #[test]
+    fn parse_missing_load() {
+        let txt = FIXTURE
+            .replacen(r#""load": 12,"#, "", 1)
+            .replacen(r#""load": 3,"#, r#""load": 0,"#, 1);
+        let mut data = Servers::from_txt(&txt).unwrap();
+        assert_eq!(data.servers[0].load, 0);
+        assert!(!data.servers[0].load_known);
+        assert_eq!(data.servers[1].load, 0);
+        assert!(data.servers[1].load_known);
+
+        // The missing load is ordered after the reported load of 0%.
+        data.deprioritize_zero_load();
+        assert_eq!(data.servers[0].domain, "ch-onion1.nordvpn.com");
+        assert_eq!(data.servers[1].domain, "nl372.nordvpn.com");
+    }
+
+    #[test]
+    fn sort_ping_with_missing_results() {
+        let mut data = Servers::from(vec![
+            loaded("nl5.nordvpn.com", 0),
+            loaded("nl4.nordvpn.com", 0),
+            loaded("nl3.nordvpn.com", 0),
+            loaded("nl2.nordvpn.com", 0),
+            loaded("nl1.nordvpn.com", 0),
+        ]);
+        // Three servers were pinged, of which one did not answer.
+        let mut results = HashMap::new();
+        results.insert("nl3.nordvpn.com".to_string(), 30_000);
+        results.insert("nl5.nordvpn.com".to_string(), 10_000);
+        data.sort_ping(&PingSorter::from(results));
+
+        assert_eq!(names(&data), vec!["nl5", "nl3", "nl1", "nl2", "nl4"]);
+
+        // Nothing was pinged at all.
+        data.sort_ping(&PingSorter::from(HashMap::<String, usize>::new()));
+        assert_eq!(names(&data), vec!["nl1", "nl2", "nl3", "nl4", "nl5"]);
+    }
+
+    #[test]
+    fn ping_keep_keeps_the_rest() {
+        use sorters::PingMethod;
+        use std::net::TcpListener;
+
+        // The first two servers answer on a local port, the others are not pinged.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = PingConfig {
+            method: PingMethod::TcpConnect,
+            port: listener.local_addr().unwrap().port(),
+            tries: 1,
+            ..PingConfig::default()
+        };
+        let mut data = Servers::from(vec![
+            loaded("127.0.0.1", 5),
+            loaded("localhost", 10),
+            loaded("nl1.nordselect.invalid", 15),
+            loaded("nl2.nordselect.invalid", 20),
+        ]);
+
+        for &threads in &[1, 2] {
+            let pings = data.ping_keep(2, &config, threads).unwrap();
+            assert_eq!(data.len(), 4);
+            assert!(pings.latency("127.0.0.1").is_some());
+            assert_eq!(pings.latency("nl1.nordselect.invalid"), None);
+            let domains: Vec<&str> = data.iter().map(|server| server.domain.as_str()).collect();
+            assert_eq!(&domains[2..], &["nl1.nordselect.invalid", "nl2.nordselect.invalid"]);
+        }
+
+        // Pinging more servers than there are is fine.
+        data.ping_keep(10, &config, 3).unwrap();
+        assert_eq!(data.len(), 4);
+        assert!(data.servers[..2].iter().all(|server| server.load <= 10));
+    }
+
+    #[test]
+    fn server_number() {
+        let number = |domain: &str| Server::builder().domain(domain).build().number();
+        assert_eq!(number("us1000.nordvpn.com"), Some(1000));
+        assert_eq!(number("nl-uk1.nordvpn.com"), None);
+        assert_eq!(number("nl.nordvpn.com"), None);
+        assert_eq!(number("1000.nordvpn.com"), None);
+        assert_eq!(number("us1000x.nordvpn.com"), None);
+        assert_eq!(number("us99999999999.nordvpn.com"), None);
+        assert_eq!(number("us1000.example.com"), None);
     }
 }
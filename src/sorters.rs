@@ -1,13 +1,17 @@
 //! Sorters are ways to sort Servers, whereas the first one is the most likely to be selected for usage.
 
+use super::icmp;
 use super::servers::{Server, Servers};
 
 use std;
 use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::iter::FromIterator;
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
 use oping::Ping;
+use reqwest;
 
 /// A Sorter is a way to order servers. It should be noted that these are sorted from best server
 /// (according to the way of sorting) to worst one.
@@ -47,6 +51,7 @@ impl Sorter for LoadSorter {
     }
 }
 
+#[derive(Clone, Default)]
 /// Sorter that sorts based on a ping-test.
 ///
 /// Please note that ping tests enhance the complexity of your program, whereas the `LoadSorter`
@@ -61,6 +66,10 @@ impl Sorter for LoadSorter {
 pub struct PingSorter {
     /// The results of the ping test.
     ping_results: HashMap<String, usize>,
+    /// The statistics of every pinged host, including those that did not answer.
+    stats: HashMap<String, PingStats>,
+    /// The errors of the hosts that could not be pinged.
+    errors: HashMap<String, String>,
 }
 
 /// Ways to set up a PingSorter.
@@ -69,71 +78,1282 @@ impl PingSorter {
     ///
     /// This function takes an Iterator for Servers
     ///
-    /// Returns an Error on failure.
+    /// Every answer is waited for at most the timeout of the default
+    /// [PingConfig](struct.PingConfig.html).
+    ///
+    /// A server that cannot be pinged does not stop the others, see [errors](#method.errors).
+    /// Returns an Error if no server could be pinged. Nothing is pinged if there are no servers.
     pub fn ping_single(
         servers: &Servers,
         tries: usize,
     ) -> Result<PingSorter, Box<dyn std::error::Error>> {
-        let mut ping_results = HashMap::new();
+        let config = PingConfig {
+            tries,
+            ..PingConfig::default()
+        };
+        let mut sorter = PingSorter::default();
+        let mut ping_results: HashMap<String, Vec<usize>> = HashMap::new();
+        // Nothing to ping, which oping would report as an error.
+        if servers.is_empty() {
+            return Ok(sorter);
+        }
         for _ in 0..tries {
             let mut pingr = Ping::new();
+            pingr.set_timeout(oping_timeout(config.timeout))?;
+            let mut added = 0;
             for ref server in &servers.servers {
-                pingr.add_host(server.domain.as_str())?;
+                match pingr.add_host(server.domain.as_str()) {
+                    Ok(()) => added += 1,
+                    // E.g. a domain that cannot be resolved.
+                    Err(error) => {
+                        sorter.errors.insert(server.domain.clone(), error.to_string());
+                    }
+                }
+            }
+            if added == 0 {
+                break;
             }
 
-            let results = pingr.send()?;
+            let results = match pingr.send() {
+                Ok(results) => results,
+                Err(ref error) if is_permission_error(error) => {
+                    let config = PingConfig {
+                        method: PingMethod::TcpConnect,
+                        ..config
+                    };
+                    return PingSorter::ping_with(servers, &config);
+                }
+                Err(error) => return Err(error.into()),
+            };
 
+            // Pings that timed out are left out, so servers that never answered are ordered last.
             for result in results {
-                let old_value: usize = *ping_results.get(&result.hostname).unwrap_or(&0usize);
-                ping_results.insert(
-                    result.hostname,
-                    old_value + (result.latency_ms * 1000f64) as usize,
-                );
+                let latencies = ping_results.entry(result.hostname).or_insert_with(Vec::new);
+                if result.dropped == 0 {
+                    latencies.push((result.latency_ms * 1000f64) as usize);
+                }
             }
         }
 
-        Ok(PingSorter {
-            ping_results: HashMap::from_iter(
-                ping_results
-                    .into_iter()
-                    .map(|(host, results)| (host, results / tries)),
-            ),
-        })
+        for (host, latencies) in ping_results {
+            sorter.record(host, &latencies, &config);
+        }
+        sorter.or_error(None)
     }
 
     /// Creates a new PingSorter using a ping instance for every server, doing tests after one another. This is more precise, but takes significantly longer.
     ///
     /// This function takes an Iterator for Servers
     ///
-    /// Returns an Error on failure.
+    /// A server that cannot be pinged does not stop the others, see [errors](#method.errors).
+    /// Returns the first error if no server could be pinged. Nothing is pinged if there are no
+    /// servers.
     pub fn ping_multi(
         servers: &Servers,
         tries: usize,
     ) -> Result<PingSorter, Box<dyn std::error::Error>> {
-        let mut ping_results = HashMap::new();
+        let config = PingConfig {
+            tries,
+            ..PingConfig::default()
+        };
+        PingSorter::ping_with(servers, &config)
+    }
+
+    /// Like [ping_multi](#method.ping_multi), but pings as set in the given `PingConfig`, e.g.
+    /// to measure the time to connect over TCP instead of using ICMP.
+    pub fn ping_with(
+        servers: &Servers,
+        config: &PingConfig,
+    ) -> Result<PingSorter, Box<dyn std::error::Error>> {
+        let mut sorter = PingSorter::default();
+        let mut first_error = None;
+        let http = http_client(config)?;
         for ref server in &servers.servers {
-            let mut sum = 0;
-            for _ in 0..tries {
-                let mut pingr = Ping::new();
-                pingr.add_host(server.domain.as_str())?;
-                sum = sum + (pingr.send()?.next().unwrap().latency_ms * 1000f64) as usize;
+            match ping_host(&server.domain, config, http.as_ref()) {
+                Ok(latencies) => sorter.record(server.domain.clone(), &latencies, config),
+                Err(error) => {
+                    sorter.errors.insert(server.domain.clone(), error.to_string());
+                    first_error = first_error.or(Some(error));
+                }
+            }
+        }
+
+        sorter.or_error(first_error)
+    }
+
+    /// Creates a new PingSorter by pinging the servers on `threads` threads at once. Every thread
+    /// pings its share of the servers after one another, like
+    /// [ping_multi](#method.ping_multi), so this is as precise but a lot faster.
+    ///
+    /// A server that cannot be pinged, or does not answer any ping within the timeout, does not
+    /// stop the others: its error is available through [errors](#method.errors), and it is
+    /// ordered last like other servers without results.
+    pub fn ping_parallel(servers: &Servers, config: &PingConfig, threads: usize) -> PingSorter {
+        PingSorter::ping_parallel_with_progress(servers, config, threads, |_| {})
+    }
+
+    /// Like [ping_parallel](#method.ping_parallel), but calls `progress` every time a server is
+    /// done, e.g. to show how many servers are left. The callback is always called from the
+    /// calling thread, so it needs no synchronization.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use nordselect::Servers;
+    /// use nordselect::sorters::{PingConfig, PingSorter};
+    /// let data = Servers::dummy_data();
+    ///
+    /// let config = PingConfig::default();
+    /// let sorter = PingSorter::ping_parallel_with_progress(&data, &config, 8, |event| {
+    ///     eprintln!("{}/{}: {}", event.index + 1, event.total, event.domain);
+    /// });
+    /// ```
+    pub fn ping_parallel_with_progress<F: FnMut(ProgressEvent)>(
+        servers: &Servers,
+        config: &PingConfig,
+        threads: usize,
+        progress: F,
+    ) -> PingSorter {
+        ping_parallel_using(servers, config, threads, ping_host, progress)
+    }
+
+    /// Stores the results of pinging a host, of which the given latencies in microseconds were
+    /// answered. A host that answered no ping is stored as an error.
+    fn record(&mut self, host: String, latencies: &[usize], config: &PingConfig) {
+        match config.aggregation.aggregate(latencies) {
+            Some(latency) => {
+                self.ping_results.insert(host.clone(), latency);
+            }
+            None => {
+                let error = format!("no answer within {:?}", config.timeout);
+                self.errors.insert(host.clone(), error);
             }
-            ping_results.insert(server.domain.clone(), sum / tries);
         }
+        self.stats.insert(host, PingStats::new(latencies, config.tries));
+    }
+
+    /// Returns this PingSorter, or an error if servers were pinged but none of them could be.
+    fn or_error(
+        self,
+        first_error: Option<Box<dyn std::error::Error>>,
+    ) -> Result<PingSorter, Box<dyn std::error::Error>> {
+        if !self.ping_results.is_empty() || self.errors.is_empty() {
+            return Ok(self);
+        }
+        Err(first_error.unwrap_or_else(|| {
+            format!("none of the {} servers could be pinged", self.errors.len()).into()
+        }))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A server that is done pinging, passed to the progress callback of
+/// [PingSorter::ping_parallel_with_progress](struct.PingSorter.html#method.ping_parallel_with_progress).
+pub struct ProgressEvent {
+    /// The domain of the server.
+    pub domain: String,
+    /// The number of servers that were done before this one. Events arrive in the order in which
+    /// the servers are done, so this counts from 0 to `total - 1`.
+    pub index: usize,
+    /// The number of servers that are pinged.
+    pub total: usize,
+    /// The latency of the server in microseconds, or the reason it could not be pinged.
+    pub result: Result<usize, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The number of servers that could be pinged, returned by
+/// [PingSorter::summary](struct.PingSorter.html#method.summary).
+pub struct PingSummary {
+    /// The number of servers with a latency.
+    pub succeeded: usize,
+    /// The number of servers that could not be pinged or did not answer.
+    pub failed: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Statistics of pinging a server several times. Latencies are in milliseconds, and only cover
+/// the pings that were answered.
+pub struct PingStats {
+    /// The average latency.
+    pub avg_ms: f64,
+    /// The lowest latency.
+    pub min_ms: f64,
+    /// The highest latency.
+    pub max_ms: f64,
+    /// The percentage of pings that were not answered.
+    pub loss_pct: f64,
+    /// The number of pings that were sent.
+    pub samples: usize,
+}
+
+impl PingStats {
+    /// Computes the statistics of `sent` pings, of which the given latencies in microseconds were
+    /// answered. The latencies are 0 if no ping was answered.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nordselect::sorters::PingStats;
+    ///
+    /// let stats = PingStats::new(&[20_000, 30_000], 4);
+    /// assert_eq!(stats.avg_ms, 25.0);
+    /// assert_eq!(stats.loss_pct, 50.0);
+    /// assert_eq!(stats.jitter_ms(), 10.0);
+    /// ```
+    pub fn new(latencies: &[usize], sent: usize) -> PingStats {
+        let samples = sent.max(latencies.len());
+        if latencies.is_empty() {
+            return PingStats {
+                avg_ms: 0.0,
+                min_ms: 0.0,
+                max_ms: 0.0,
+                loss_pct: if samples > 0 { 100.0 } else { 0.0 },
+                samples,
+            };
+        }
+        let ms: Vec<f64> = latencies.iter().map(|&latency| latency as f64 / 1000.0).collect();
+        PingStats {
+            avg_ms: ms.iter().sum::<f64>() / ms.len() as f64,
+            min_ms: ms.iter().cloned().fold(std::f64::INFINITY, f64::min),
+            max_ms: ms.iter().cloned().fold(0.0, f64::max),
+            loss_pct: (samples - ms.len()) as f64 * 100.0 / samples as f64,
+            samples,
+        }
+    }
+
+    /// Returns the difference between the highest and the lowest latency.
+    pub fn jitter_ms(&self) -> f64 {
+        self.max_ms - self.min_ms
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// How servers are pinged by
+/// [PingSorter::ping_parallel](struct.PingSorter.html#method.ping_parallel).
+///
+/// # Example
+///
+/// ```
+/// use nordselect::sorters::PingConfig;
+/// use std::time::Duration;
+///
+/// // Give up on a server after 3 pings of 300 ms.
+/// let config = PingConfig {
+///     timeout: Duration::from_millis(300),
+///     tries: 3,
+///     ..PingConfig::default()
+/// };
+/// ```
+pub struct PingConfig {
+    /// How long to wait for the answer to a single ping. A ping that is not answered in time is
+    /// a failed try.
+    pub timeout: Duration,
+    /// How many times every server is pinged.
+    pub tries: usize,
+    /// How the latencies of the tries of a server are combined.
+    pub aggregation: Aggregation,
+    /// How the latency is measured.
+    pub method: PingMethod,
+    /// The port to connect to when using `PingMethod::TcpConnect`.
+    pub port: u16,
+    /// Whether `PingMethod::Https` accepts invalid certificates. The servers present a valid
+    /// certificate for `*.nordvpn.com`, so this is only needed for servers without one.
+    pub accept_invalid_certs: bool,
+    /// How long to wait between the pings to a server, as some servers limit the rate of pings.
+    pub interval: Duration,
+    /// Whether to send an extra ping before the others, of which the latency is not used. The
+    /// first ping is often slower, as the route to the server still has to be set up.
+    pub discard_first: bool,
+}
+
+impl Default for PingConfig {
+    /// Pings every server twice using ICMP, waiting at most 1 second for every answer, like the
+    /// CLI, and uses the median latency. Pings are sent right after one another, without a
+    /// warm-up. TCP connections are made to port 443, which is used by
+    /// OpenVPN over TCP.
+    fn default() -> PingConfig {
+        PingConfig {
+            timeout: Duration::from_secs(1),
+            tries: 2,
+            aggregation: Aggregation::default(),
+            method: PingMethod::Icmp,
+            port: 443,
+            accept_invalid_certs: false,
+            interval: Duration::from_secs(0),
+            discard_first: false,
+        }
+    }
+}
 
-        Ok(PingSorter { ping_results })
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A way to measure the latency of a server.
+pub enum PingMethod {
+    /// An ICMP echo request, sent using oping. This needs special privileges, see
+    /// [PingSorter](struct.PingSorter.html). Without them, `TcpConnect` is used instead.
+    Icmp,
+    /// The time to open a TCP connection to the port set in the `PingConfig`. This needs no
+    /// privileges, but also includes the time the server takes to accept the connection. A
+    /// refused connection still measures the round trip to the server.
+    TcpConnect,
+    /// An ICMP echo request over an unprivileged datagram socket, which Linux allows for the
+    /// groups in `net.ipv4.ping_group_range`. Only IPv4 addresses are pinged. If such a socket
+    /// cannot be created, e.g. on other platforms, `TcpConnect` is used instead.
+    UnprivilegedIcmp,
+    /// The time until the response to a HEAD request to `https://<domain>/` arrives, including
+    /// setting up the connection. Some servers answer ICMP slower than other traffic, which this
+    /// does not suffer from.
+    ///
+    /// The requests are sent directly to the servers, never through a proxy such as the one of an
+    /// [ApiConfig](../api/struct.ApiConfig.html).
+    Https,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How the latencies of several pings to the same server are combined into one.
+pub enum Aggregation {
+    /// The average latency.
+    Mean,
+    /// The middle latency, or the average of both middle latencies for an even number of pings.
+    /// Unlike the mean, a single slow ping does not change the result much.
+    Median,
+    /// The lowest latency.
+    Min,
+}
+
+impl Default for Aggregation {
+    fn default() -> Aggregation {
+        Aggregation::Median
+    }
+}
+
+impl Aggregation {
+    /// Combines the given latencies into one. Returns `None` if there are no latencies.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use nordselect::sorters::Aggregation;
+    ///
+    /// let latencies = [12_000, 300_000, 14_000];
+    /// assert_eq!(Aggregation::Mean.aggregate(&latencies), Some(108_666));
+    /// assert_eq!(Aggregation::Median.aggregate(&latencies), Some(14_000));
+    /// assert_eq!(Aggregation::Min.aggregate(&latencies), Some(12_000));
+    /// ```
+    pub fn aggregate(self, latencies: &[usize]) -> Option<usize> {
+        if latencies.is_empty() {
+            return None;
+        }
+        Some(match self {
+            Aggregation::Mean => latencies.iter().sum::<usize>() / latencies.len(),
+            Aggregation::Median => {
+                let mut sorted = latencies.to_vec();
+                sorted.sort_unstable();
+                let middle = sorted.len() / 2;
+                if sorted.len() % 2 == 0 {
+                    (sorted[middle - 1] + sorted[middle]) / 2
+                } else {
+                    sorted[middle]
+                }
+            }
+            Aggregation::Min => *latencies.iter().min().unwrap(),
+        })
+    }
+}
+
+/// Pings a host as set in the `PingConfig` using the client for `PingMethod::Https`, returning
+/// the latencies in microseconds.
+type HostPinger = fn(
+    &str,
+    &PingConfig,
+    Option<&reqwest::Client>,
+) -> Result<Vec<usize>, Box<dyn std::error::Error>>;
+
+/// Pings the servers on `threads` threads using the given function, calling `progress` on the
+/// calling thread as soon as a server is done.
+fn ping_parallel_using<F: FnMut(ProgressEvent)>(
+    servers: &Servers,
+    config: &PingConfig,
+    threads: usize,
+    ping: HostPinger,
+    mut progress: F,
+) -> PingSorter {
+    let domains: Vec<String> = servers.iter().map(|server| server.domain.clone()).collect();
+    let total = domains.len();
+    let http = match http_client(config) {
+        Ok(http) => http,
+        Err(error) => {
+            // No server can be pinged, which is reported like any other error.
+            let error = error.to_string();
+            let mut sorter = PingSorter::default();
+            for (index, domain) in domains.into_iter().enumerate() {
+                sorter.errors.insert(domain.clone(), error.clone());
+                progress(ProgressEvent {
+                    domain,
+                    index,
+                    total,
+                    result: Err(error.clone()),
+                });
+            }
+            return sorter;
+        }
+    };
+    let threads = threads.max(1);
+    let chunk_size = (total + threads - 1) / threads;
+    let (sender, receiver) = mpsc::channel();
+    for chunk in domains.chunks(chunk_size.max(1)) {
+        let chunk = chunk.to_vec();
+        let config = config.clone();
+        let http = http.clone();
+        let sender = sender.clone();
+        std::thread::spawn(move || {
+            for domain in chunk {
+                let result =
+                    ping(&domain, &config, http.as_ref()).map_err(|error| error.to_string());
+                if sender.send((domain, result)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+    // The loop below ends once every thread is done. A thread only panics on a bug in oping, and
+    // then its remaining servers have no results.
+    drop(sender);
+
+    let mut sorter = PingSorter::default();
+    for (index, (domain, result)) in receiver.iter().enumerate() {
+        match result {
+            Ok(latencies) => sorter.record(domain.clone(), &latencies, config),
+            Err(error) => {
+                sorter.errors.insert(domain.clone(), error);
+            }
+        }
+        let result = sorter
+            .latency(&domain)
+            .ok_or_else(|| sorter.errors.get(&domain).cloned().unwrap_or_default());
+        progress(ProgressEvent {
+            domain,
+            index,
+            total,
+            result,
+        });
+    }
+    sorter
+}
+
+/// Measures the latency of a host once, returning it in microseconds, or `None` if the host did
+/// not answer within the timeout.
+type Probe<'a> =
+    dyn Fn(&str, &PingConfig) -> Result<Option<usize>, Box<dyn std::error::Error>> + 'a;
+
+/// Pings the host `tries` times after one another, returning the latencies of the pings that were
+/// answered within the timeout, in microseconds. `PingMethod::Https` uses the given client, made
+/// by `http_client`.
+fn ping_host(
+    domain: &str,
+    config: &PingConfig,
+    http: Option<&reqwest::Client>,
+) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+    ping_host_with(domain, config, &icmp_probe, &tcp_probe, http)
+}
+
+/// Like `ping_host`, using the given probes. Switches to the TCP probe if the ICMP probe is not
+/// permitted.
+fn ping_host_with(
+    domain: &str,
+    config: &PingConfig,
+    icmp: &Probe,
+    tcp: &Probe,
+    http: Option<&reqwest::Client>,
+) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+    let mut method = config.method;
+    let mut latencies = Vec::with_capacity(config.tries);
+    let warm_up = if config.discard_first { 1 } else { 0 };
+    for try_number in 0..config.tries + warm_up {
+        if try_number > 0 && config.interval > Duration::from_secs(0) {
+            std::thread::sleep(config.interval);
+        }
+        let latency = match method {
+            PingMethod::Icmp => match icmp(domain, config) {
+                Err(ref error) if is_permission_error(&**error) => {
+                    method = PingMethod::TcpConnect;
+                    tcp(domain, config)?
+                }
+                result => result?,
+            },
+            PingMethod::TcpConnect => tcp(domain, config)?,
+            PingMethod::UnprivilegedIcmp => unprivileged_icmp_probe(domain, config)?,
+            PingMethod::Https => {
+                let client = http.ok_or("no HTTP client to ping with")?;
+                http_probe(client, &format!("https://{}/", domain))?
+            }
+        };
+        if try_number >= warm_up {
+            latencies.extend(latency);
+        }
+    }
+    Ok(latencies)
+}
+
+/// Returns whether the error means that the program may not send ICMP packets.
+fn is_permission_error<E: std::error::Error + ?Sized>(error: &E) -> bool {
+    let message = error.to_string();
+    message.contains("Operation not permitted") || message.contains("Permission denied")
+}
+
+/// Returns the given timeout in seconds, as expected by oping.
+fn oping_timeout(timeout: Duration) -> f64 {
+    timeout.as_secs() as f64 + f64::from(timeout.subsec_nanos()) / 1e9
+}
+
+/// Sends an ICMP echo request using oping.
+fn icmp_probe(
+    domain: &str,
+    config: &PingConfig,
+) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+    let mut pingr = Ping::new();
+    pingr.set_timeout(oping_timeout(config.timeout))?;
+    pingr.add_host(domain)?;
+    let result = pingr.send()?.next().ok_or("no ping response")?;
+    if result.dropped == 0 {
+        Ok(Some((result.latency_ms * 1000f64) as usize))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Opens a TCP connection to the configured port of the host.
+fn tcp_probe(
+    domain: &str,
+    config: &PingConfig,
+) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+    let address = (domain, config.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or("no address for host")?;
+    let start = Instant::now();
+    let result = TcpStream::connect_timeout(&address, config.timeout);
+    let elapsed = start.elapsed();
+    match result {
+        Ok(_) => {}
+        Err(ref error) if error.kind() == std::io::ErrorKind::ConnectionRefused => {}
+        Err(ref error) if error.kind() == std::io::ErrorKind::TimedOut => return Ok(None),
+        Err(error) => return Err(error.into()),
+    }
+    Ok(Some(micros(elapsed)))
+}
+
+/// Sends an ICMP echo request over an unprivileged socket, or opens a TCP connection if such a
+/// socket cannot be created.
+fn unprivileged_icmp_probe(
+    domain: &str,
+    config: &PingConfig,
+) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+    let socket = match icmp::Socket::new() {
+        Ok(socket) => socket,
+        Err(_) => return tcp_probe(domain, config),
+    };
+    let address = (domain, 0)
+        .to_socket_addrs()?
+        .filter_map(|address| match address.ip() {
+            IpAddr::V4(ip) => Some(ip),
+            IpAddr::V6(_) => None,
+        })
+        .next()
+        .ok_or("no IPv4 address for host")?;
+    let rtt = socket.ping(address, icmp::next_sequence(), config.timeout)?;
+    Ok(rtt.map(micros))
+}
+
+/// Builds the client to ping with when using `PingMethod::Https`, which is shared by all pings of
+/// a call. Returns `None` for the other methods, as building a client starts a thread.
+///
+/// The requests are not sent through the proxy of an `ApiConfig`, as that would measure the
+/// latency of the proxy instead of the server.
+fn http_client(config: &PingConfig) -> reqwest::Result<Option<reqwest::Client>> {
+    if config.method != PingMethod::Https {
+        return Ok(None);
+    }
+    let client = reqwest::Client::builder()
+        .timeout(config.timeout)
+        .danger_accept_invalid_certs(config.accept_invalid_certs)
+        .build()?;
+    Ok(Some(client))
+}
+
+/// Sends a HEAD request to the URL using the client, measuring the time until the response
+/// arrives. Any status counts as an answer, and no answer within the timeout of the client as
+/// `None`.
+fn http_probe(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+    let start = Instant::now();
+    match client.head(url).send() {
+        Ok(_) => Ok(Some(micros(start.elapsed()))),
+        Err(ref error) if error.is_timeout() => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Returns the duration in microseconds.
+fn micros(duration: Duration) -> usize {
+    duration.as_secs() as usize * 1_000_000 + duration.subsec_micros() as usize
+}
+
+/// Access to the results of the ping test.
+impl PingSorter {
+    /// Returns the latency of the server with the given domain, in microseconds. The latencies of
+    /// several pings are combined as set in the [PingConfig](struct.PingConfig.html).
+    ///
+    /// Returns `None` if the server was not pinged or never answered.
+    pub fn latency(&self, domain: &str) -> Option<usize> {
+        self.ping_results.get(domain).cloned()
+    }
+
+    /// Returns the statistics of pinging the server with the given domain, including its packet
+    /// loss.
+    ///
+    /// Returns `None` if the server was not pinged.
+    pub fn stats(&self, domain: &str) -> Option<&PingStats> {
+        self.stats.get(domain)
+    }
+
+    /// Returns the errors of the servers that could not be pinged or did not answer, by domain.
+    /// These servers are ordered last.
+    pub fn errors(&self) -> &HashMap<String, String> {
+        &self.errors
+    }
+
+    /// Returns how many servers could be pinged and how many could not, e.g. to log it.
+    pub fn summary(&self) -> PingSummary {
+        PingSummary {
+            succeeded: self.ping_results.len(),
+            failed: self
+                .errors
+                .keys()
+                .filter(|domain| !self.ping_results.contains_key(*domain))
+                .count(),
+        }
+    }
+}
+
+/// Creates a PingSorter from existing results, mapping domains to their latency in microseconds.
+impl From<HashMap<String, usize>> for PingSorter {
+    fn from(ping_results: HashMap<String, usize>) -> PingSorter {
+        PingSorter {
+            ping_results,
+            stats: HashMap::new(),
+            errors: HashMap::new(),
+        }
+    }
+}
+
+/// Creates a PingSorter from existing statistics, by domain. The latency of a server is its
+/// average latency, unless it answered no ping.
+impl From<HashMap<String, PingStats>> for PingSorter {
+    fn from(stats: HashMap<String, PingStats>) -> PingSorter {
+        let ping_results = stats
+            .iter()
+            .filter(|&(_, stats)| stats.loss_pct < 100.0)
+            .map(|(domain, stats)| (domain.clone(), (stats.avg_ms * 1000.0) as usize))
+            .collect();
+        PingSorter {
+            ping_results,
+            stats,
+            errors: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The weights of the score used by
+/// [Servers::sort_score](../servers/struct.Servers.html#method.sort_score), which combines the
+/// load and the latency of a server: `load * load% + ping * latency in ms`. Lower scores are
+/// better. This is a [WeightedScore](struct.WeightedScore.html) of a
+/// [LoadScore](struct.LoadScore.html) and a [PingScore](struct.PingScore.html), see
+/// [with_pings](#method.with_pings).
+///
+/// # Example
+///
+/// ```
+/// use nordselect::sorters::ScoreWeights;
+///
+/// // Only a large difference in latency outweighs the load.
+/// let weights = ScoreWeights {
+///     ping: 0.1,
+///     ..ScoreWeights::default()
+/// };
+/// ```
+pub struct ScoreWeights {
+    /// The weight of the load, in percent.
+    pub load: f64,
+    /// The weight of the latency, in milliseconds.
+    pub ping: f64,
+    /// The latency used for servers that were not pinged, in milliseconds.
+    pub missing_ping: f64,
+}
+
+impl Default for ScoreWeights {
+    /// Weighs 1% of load like 1 ms of latency, and treats servers that were not pinged as having a
+    /// latency of 1 second. Like pinging the least loaded servers, this selects a server with a
+    /// low load, unless a slightly busier one is a lot closer.
+    fn default() -> ScoreWeights {
+        ScoreWeights {
+            load: 1.0,
+            ping: 1.0,
+            missing_ping: 1000.0,
+        }
+    }
+}
+
+impl ScoreWeights {
+    /// Returns the score with these weights, using the given ping results.
+    pub fn with_pings(&self, pings: PingSorter) -> WeightedScore {
+        WeightedScore::from(vec![
+            (self.load, Box::new(LoadScore) as Box<dyn Score>),
+            (self.ping, Box::new(PingScore::new(pings, self.missing_ping))),
+        ])
     }
 }
 
 impl Sorter for PingSorter {
+    /// Servers that were not pinged, e.g. because they did not respond, are ordered last. Servers
+    /// that lost fewer pings come first, as a server that drops pings is unreliable even when its
+    /// latency is low. Servers with the same loss are ordered on their latency.
     fn sort(&self, a: &Server, b: &Server) -> Ordering {
-        self.ping_results
-            .get(a.domain.as_str())
-            .expect("Server not found in ping result")
-            .cmp(
-                self.ping_results
-                    .get(b.domain.as_str())
-                    .expect("Other server not found in ping result"),
-            )
+        let loss = |server: &Server| self.stats(&server.domain).map_or(0.0, |stats| stats.loss_pct);
+        match (self.latency(&a.domain), self.latency(&b.domain)) {
+            (Some(latency_a), Some(latency_b)) => loss(a)
+                .partial_cmp(&loss(b))
+                .unwrap_or(Ordering::Equal)
+                .then(latency_a.cmp(&latency_b)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+/// A way to rank servers by a number, where lower scores are better. Used by
+/// [Servers::sort_by_score](../servers/struct.Servers.html#method.sort_by_score).
+///
+/// Unlike a [Sorter](trait.Sorter.html), scores can be combined using a
+/// [WeightedScore](struct.WeightedScore.html).
+///
+/// # Example
+///
+/// ```
+/// use nordselect::{Servers, Server, sorters::Score};
+/// let mut data = Servers::dummy_data();
+///
+/// // Demo score: prefers servers in the Netherlands.
+/// struct DutchScore;
+///
+/// impl Score for DutchScore {
+///     fn score(&self, server: &Server) -> f64 {
+///         if server.flag == "NL" { 0.0 } else { 1.0 }
+///     }
+/// }
+///
+/// data.sort_by_score(&DutchScore);
+/// assert_eq!(data.perfect_server().unwrap().flag, "NL");
+/// ```
+pub trait Score {
+    /// Returns the score of the server. Lower scores are better.
+    fn score(&self, &Server) -> f64;
+}
+
+/// Score that is the load of a server, in percent.
+pub struct LoadScore;
+
+impl Score for LoadScore {
+    fn score(&self, server: &Server) -> f64 {
+        f64::from(server.load)
+    }
+}
+
+/// Score that is the latency of a server according to a ping test, in milliseconds.
+pub struct PingScore {
+    /// The results of the ping test.
+    pings: PingSorter,
+    /// The latency used for servers that were not pinged, in milliseconds.
+    missing_ping: f64,
+}
+
+/// Ways to construct a PingScore.
+impl PingScore {
+    /// Creates a PingScore using the given latency, in milliseconds, for servers that were not
+    /// pinged.
+    pub fn new(pings: PingSorter, missing_ping: f64) -> PingScore {
+        PingScore {
+            pings,
+            missing_ping,
+        }
+    }
+}
+
+/// Creates a PingScore that treats servers that were not pinged as having a latency of 1 second.
+impl From<PingSorter> for PingScore {
+    fn from(pings: PingSorter) -> PingScore {
+        PingScore::new(pings, ScoreWeights::default().missing_ping)
+    }
+}
+
+impl Score for PingScore {
+    fn score(&self, server: &Server) -> f64 {
+        self.pings
+            .latency(&server.domain)
+            .map_or(self.missing_ping, |latency| latency as f64 / 1000.0)
+    }
+}
+
+/// Score that is the weighted sum of other scores.
+///
+/// # Example
+///
+/// ```
+/// use nordselect::Servers;
+/// use nordselect::sorters::{LoadScore, PingScore, PingSorter, Score, WeightedScore};
+/// use std::collections::HashMap;
+/// let mut data = Servers::dummy_data();
+///
+/// let mut results = HashMap::new();
+/// results.insert("be1.nordvpn.com".to_string(), 15_000);
+/// let score = WeightedScore::from(vec![
+///     (1.0, Box::new(LoadScore) as Box<dyn Score>),
+///     (0.5, Box::new(PingScore::from(PingSorter::from(results)))),
+/// ]);
+/// data.sort_by_score(&score);
+/// ```
+pub struct WeightedScore {
+    /// The scores and their weights.
+    scores: Vec<(f64, Box<dyn Score>)>,
+}
+
+impl From<Vec<(f64, Box<dyn Score>)>> for WeightedScore {
+    fn from(scores: Vec<(f64, Box<dyn Score>)>) -> WeightedScore {
+        WeightedScore { scores }
+    }
+}
+
+impl Score for WeightedScore {
+    fn score(&self, server: &Server) -> f64 {
+        self.scores
+            .iter()
+            .map(|&(weight, ref score)| weight * score.score(server))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ping_nothing() {
+        // No ping privileges are needed, as nothing is pinged.
+        let empty = Servers::from(Vec::new());
+        let sorter = PingSorter::ping_single(&empty, 3).unwrap();
+        assert!(sorter.ping_results.is_empty());
+        let sorter = PingSorter::ping_multi(&empty, 3).unwrap();
+        assert!(sorter.ping_results.is_empty());
+        let sorter = PingSorter::ping_parallel(&empty, &PingConfig::default(), 4);
+        assert!(sorter.ping_results.is_empty() && sorter.errors().is_empty());
+    }
+
+    #[test]
+    fn ping_parallel_collects_errors() {
+        // Whether these hosts cannot be resolved or pinging is not permitted, every host fails on
+        // its own without stopping the others.
+        let data = Servers::from(vec![
+            Server::builder().domain("nl1.nordselect.invalid").build(),
+            Server::builder().domain("nl2.nordselect.invalid").build(),
+            Server::builder().domain("nl3.nordselect.invalid").build(),
+        ]);
+
+        let sorter = PingSorter::ping_parallel(&data, &PingConfig::default(), 2);
+        assert!(sorter.ping_results.is_empty());
+        assert_eq!(sorter.errors().len(), 3);
+        assert!(sorter.errors().contains_key("nl3.nordselect.invalid"));
+        assert_eq!(
+            sorter.summary(),
+            PingSummary {
+                succeeded: 0,
+                failed: 3
+            }
+        );
+
+        // The other ways of pinging only fail if no server could be pinged.
+        assert!(PingSorter::ping_multi(&data, 1).is_err());
+        assert!(PingSorter::ping_single(&data, 1).is_err());
+    }
+
+    #[test]
+    fn failed_servers_last() {
+        let mut data = Servers::from(vec![
+            Server::builder().domain("nl1.nordselect.invalid").build(),
+            Server::builder().domain("nl2.nordvpn.com").build(),
+            Server::builder().domain("192.0.2.1").build(),
+            Server::builder().domain("nl3.nordvpn.com").build(),
+        ]);
+        let mut sorter = PingSorter::default();
+        let config = PingConfig::default();
+        sorter.record("nl2.nordvpn.com".to_string(), &[30_000, 32_000], &config);
+        sorter.record("192.0.2.1".to_string(), &[], &config);
+        sorter.record("nl3.nordvpn.com".to_string(), &[12_000, 14_000], &config);
+        let error = "unknown host".to_string();
+        sorter.errors.insert("nl1.nordselect.invalid".to_string(), error);
+
+        assert!(sorter.errors()["192.0.2.1"].contains("no answer"));
+        assert_eq!(
+            sorter.summary(),
+            PingSummary {
+                succeeded: 2,
+                failed: 2
+            }
+        );
+
+        // The failed servers are ordered last, on their name.
+        data.sort_stable(&sorter);
+        let domains: Vec<&str> = data.iter().map(|server| server.domain.as_str()).collect();
+        assert_eq!(
+            domains,
+            vec![
+                "nl3.nordvpn.com",
+                "nl2.nordvpn.com",
+                "192.0.2.1",
+                "nl1.nordselect.invalid"
+            ]
+        );
+        assert!(sorter.or_error(None).is_ok());
+    }
+
+    #[test]
+    fn unpinged_servers_last() {
+        let mut data = Servers::from(vec![
+            Server::builder().domain("nl1.nordvpn.com").build(),
+            Server::builder().domain("nl2.nordvpn.com").build(),
+            Server::builder().domain("nl3.nordvpn.com").build(),
+        ]);
+        let mut results = HashMap::new();
+        results.insert("nl2.nordvpn.com".to_string(), 30_000);
+        results.insert("nl3.nordvpn.com".to_string(), 10_000);
+
+        data.sort(&PingSorter::from(results));
+        let domains: Vec<&str> = data.iter().map(|server| server.domain.as_str()).collect();
+        assert_eq!(
+            domains,
+            vec!["nl3.nordvpn.com", "nl2.nordvpn.com", "nl1.nordvpn.com"]
+        );
+    }
+
+    /// Prefers servers in the given datacenter, recognised by the start of their IP address.
+    struct DatacenterScore(&'static str);
+
+    impl Score for DatacenterScore {
+        fn score(&self, server: &Server) -> f64 {
+            if server.ip_address.starts_with(self.0) {
+                0.0
+            } else {
+                100.0
+            }
+        }
+    }
+
+    #[test]
+    fn custom_weighted_score() {
+        let server = |domain: &str, load: u8, ip_address: &str| {
+            Server::builder()
+                .domain(domain)
+                .load(load)
+                .ip_address(ip_address)
+                .build()
+        };
+        let mut data = Servers::from(vec![
+            server("nl1.nordvpn.com", 40, "10.0.0.1"),
+            server("nl2.nordvpn.com", 10, "10.1.0.1"),
+            server("nl3.nordvpn.com", 40, "10.0.0.2"),
+            server("nl4.nordvpn.com", 30, "10.0.0.3"),
+        ]);
+        let mut results = HashMap::new();
+        results.insert("nl4.nordvpn.com".to_string(), 20_000);
+        results.insert("nl1.nordvpn.com".to_string(), 5_000);
+        results.insert("nl3.nordvpn.com".to_string(), 5_000);
+
+        let score = WeightedScore::from(vec![
+            (1.0, Box::new(LoadScore) as Box<dyn Score>),
+            (1.0, Box::new(DatacenterScore("10.0."))),
+            (0.5, Box::new(PingScore::new(PingSorter::from(results), 500.0))),
+        ]);
+        assert_eq!(score.score(&data.servers[0]), 42.5);
+
+        // nl1 and nl3 have the same score, so they keep their order.
+        data.sort_by_score(&score);
+        let names: Vec<&str> = data.iter().map(|server| server.name().unwrap()).collect();
+        assert_eq!(names, vec!["nl4", "nl1", "nl3", "nl2"]);
+    }
+
+    #[test]
+    fn score_weights_with_pings() {
+        let server = |domain: &str| Server::builder().domain(domain).load(20).build();
+        let mut results = HashMap::new();
+        results.insert("nl1.nordvpn.com".to_string(), 30_000);
+        let weights = ScoreWeights {
+            load: 2.0,
+            ping: 0.5,
+            missing_ping: 100.0,
+        };
+
+        let score = weights.with_pings(PingSorter::from(results));
+        assert_eq!(score.score(&server("nl1.nordvpn.com")), 55.0);
+        assert_eq!(score.score(&server("nl2.nordvpn.com")), 90.0);
+    }
+
+    #[test]
+    fn ping_timeout() {
+        use std::time::Instant;
+
+        // An address reserved for documentation (RFC 5737), which never answers.
+        let data = Servers::from(vec![Server::builder().domain("192.0.2.1").build()]);
+        let config = PingConfig {
+            timeout: Duration::from_millis(200),
+            tries: 2,
+            ..PingConfig::default()
+        };
+
+        let start = Instant::now();
+        let sorter = PingSorter::ping_parallel(&data, &config, 1);
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert_eq!(sorter.latency("192.0.2.1"), None);
+        // Without permission to ping, the error is reported instead of the timeout.
+        assert!(sorter.errors().contains_key("192.0.2.1"));
+    }
+
+    #[test]
+    fn aggregate_latencies() {
+        // One slow ping among three.
+        let outlier = [20_000, 300_000, 22_000];
+        assert_eq!(Aggregation::Mean.aggregate(&outlier), Some(114_000));
+        assert_eq!(Aggregation::Median.aggregate(&outlier), Some(22_000));
+        assert_eq!(Aggregation::Min.aggregate(&outlier), Some(20_000));
+
+        let even = [40_000, 10_000, 30_000, 20_000];
+        assert_eq!(Aggregation::Mean.aggregate(&even), Some(25_000));
+        assert_eq!(Aggregation::Median.aggregate(&even), Some(25_000));
+        assert_eq!(Aggregation::Min.aggregate(&even), Some(10_000));
+
+        assert_eq!(Aggregation::Median.aggregate(&[7_000]), Some(7_000));
+        assert_eq!(Aggregation::Mean.aggregate(&[]), None);
+        assert_eq!(Aggregation::default(), Aggregation::Median);
+    }
+
+    #[test]
+    fn ping_stats() {
+        let stats = PingStats::new(&[20_000, 10_000, 30_000], 4);
+        assert_eq!(stats.avg_ms, 20.0);
+        assert_eq!(stats.min_ms, 10.0);
+        assert_eq!(stats.max_ms, 30.0);
+        assert_eq!(stats.jitter_ms(), 20.0);
+        assert_eq!(stats.loss_pct, 25.0);
+        assert_eq!(stats.samples, 4);
+
+        let lost = PingStats::new(&[], 3);
+        assert_eq!(lost.loss_pct, 100.0);
+        assert_eq!(lost.avg_ms, 0.0);
+    }
+
+    #[test]
+    fn loss_beats_latency() {
+        let mut data = Servers::from(vec![
+            Server::builder().domain("nl1.nordvpn.com").build(),
+            Server::builder().domain("nl2.nordvpn.com").build(),
+            Server::builder().domain("nl3.nordvpn.com").build(),
+            Server::builder().domain("nl4.nordvpn.com").build(),
+        ]);
+        let mut stats = HashMap::new();
+        // Fast, but drops half of the pings.
+        stats.insert("nl1.nordvpn.com".to_string(), PingStats::new(&[20_000, 20_000], 4));
+        stats.insert("nl2.nordvpn.com".to_string(), PingStats::new(&[25_000, 25_000], 2));
+        stats.insert("nl3.nordvpn.com".to_string(), PingStats::new(&[], 2));
+        stats.insert("nl4.nordvpn.com".to_string(), PingStats::new(&[30_000, 30_000], 2));
+        let sorter = PingSorter::from(stats);
+
+        assert_eq!(sorter.latency("nl1.nordvpn.com"), Some(20_000));
+        assert_eq!(sorter.latency("nl3.nordvpn.com"), None);
+        assert_eq!(sorter.stats("nl3.nordvpn.com").unwrap().loss_pct, 100.0);
+
+        data.sort(&sorter);
+        let names: Vec<&str> = data.iter().map(|server| server.name().unwrap()).collect();
+        assert_eq!(names, vec!["nl2", "nl4", "nl1", "nl3"]);
+    }
+
+    #[test]
+    fn tcp_probe_local_listener() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = PingConfig {
+            method: PingMethod::TcpConnect,
+            port: listener.local_addr().unwrap().port(),
+            ..PingConfig::default()
+        };
+
+        let latency = tcp_probe("127.0.0.1", &config).unwrap().unwrap();
+        assert!(latency < 1_000_000);
+
+        let data = Servers::from(vec![Server::builder().domain("127.0.0.1").build()]);
+        let sorter = PingSorter::ping_with(&data, &config).unwrap();
+        assert!(sorter.latency("127.0.0.1").is_some());
+        assert_eq!(sorter.stats("127.0.0.1").unwrap().samples, 2);
+    }
+
+    #[test]
+    fn falls_back_to_tcp() {
+        use std::cell::Cell;
+
+        type ProbeResult = Result<Option<usize>, Box<dyn std::error::Error>>;
+        let icmp_calls = Cell::new(0);
+        let not_permitted = |_: &str, _: &PingConfig| -> ProbeResult {
+            icmp_calls.set(icmp_calls.get() + 1);
+            Err("oping::PingError::LibOpingError: Operation not permitted".into())
+        };
+        let unreachable = |_: &str, _: &PingConfig| -> ProbeResult {
+            Err("oping::PingError::LibOpingError: Network is unreachable".into())
+        };
+        let tcp = |_: &str, _: &PingConfig| -> ProbeResult { Ok(Some(5_000)) };
+        let config = PingConfig {
+            tries: 3,
+            ..PingConfig::default()
+        };
+
+        let latencies =
+            ping_host_with("nl1.nordvpn.com", &config, &not_permitted, &tcp, None).unwrap();
+        assert_eq!(latencies, vec![5_000, 5_000, 5_000]);
+        assert_eq!(icmp_calls.get(), 1);
+
+        // Other errors are not solved by using TCP.
+        assert!(ping_host_with("nl1.nordvpn.com", &config, &unreachable, &tcp, None).is_err());
+    }
+
+    #[test]
+    fn http_probe_local_server() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+        use std::thread;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            // The first request is answered, the second one never.
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut line = String::from("request");
+            while line.trim() != "" {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+            }
+            let response =
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+            reader.into_inner().write_all(response.as_bytes()).unwrap();
+            listener.accept().unwrap()
+        });
+        let config = PingConfig {
+            timeout: Duration::from_millis(300),
+            method: PingMethod::Https,
+            ..PingConfig::default()
+        };
+
+        // Both requests use the same client.
+        let client = http_client(&config).unwrap().unwrap();
+        let latency = http_probe(&client, &url).unwrap().unwrap();
+        assert!(latency < 300_000);
+        assert_eq!(http_probe(&client, &url).unwrap(), None);
+        assert!(http_client(&PingConfig::default()).unwrap().is_none());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn interval_and_warm_up() {
+        use std::cell::Cell;
+
+        type ProbeResult = Result<Option<usize>, Box<dyn std::error::Error>>;
+        let calls = Cell::new(0);
+        // The first ping is slow, the others take 10 ms.
+        let probe = |_: &str, _: &PingConfig| -> ProbeResult {
+            calls.set(calls.get() + 1);
+            Ok(Some(if calls.get() == 1 { 500_000 } else { 10_000 }))
+        };
+        let config = PingConfig {
+            tries: 3,
+            method: PingMethod::TcpConnect,
+            interval: Duration::from_millis(20),
+            discard_first: true,
+            ..PingConfig::default()
+        };
+
+        let start = Instant::now();
+        let latencies = ping_host_with("nl1.nordvpn.com", &config, &probe, &probe, None).unwrap();
+        assert_eq!(latencies, vec![10_000, 10_000, 10_000]);
+        assert_eq!(calls.get(), 4);
+        // Three intervals between four pings.
+        assert!(start.elapsed() >= Duration::from_millis(60));
+
+        calls.set(0);
+        let config = PingConfig {
+            tries: 3,
+            method: PingMethod::TcpConnect,
+            ..PingConfig::default()
+        };
+        let latencies = ping_host_with("nl1.nordvpn.com", &config, &probe, &probe, None).unwrap();
+        assert_eq!(latencies, vec![500_000, 10_000, 10_000]);
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn progress_events() {
+        fn fake_ping(
+            domain: &str,
+            _: &PingConfig,
+            _: Option<&reqwest::Client>,
+        ) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+            if domain.starts_with("down") {
+                return Err("unreachable".into());
+            }
+            Ok(vec![domain.len() * 1_000])
+        }
+
+        let domains = vec!["nl1.nordvpn.com", "down1.nordvpn.com", "be12.nordvpn.com", "de1.nl"];
+        let data = Servers::from(
+            domains
+                .iter()
+                .map(|domain| Server::builder().domain(domain).build())
+                .collect::<Vec<_>>(),
+        );
+        let mut events = Vec::new();
+        let sorter = ping_parallel_using(&data, &PingConfig::default(), 3, fake_ping, |event| {
+            events.push(event)
+        });
+
+        // Every server is reported once, counting up.
+        let indices: Vec<usize> = events.iter().map(|event| event.index).collect();
+        assert_eq!(indices, vec![0, 1, 2, 3]);
+        assert!(events.iter().all(|event| event.total == 4));
+        let mut reported: Vec<&str> = events.iter().map(|event| event.domain.as_str()).collect();
+        reported.sort();
+        let mut expected = domains.clone();
+        expected.sort();
+        assert_eq!(reported, expected);
+
+        for event in &events {
+            assert_eq!(event.result.as_ref().ok(), sorter.latency(&event.domain).as_ref());
+        }
+        let down = events.iter().find(|event| event.domain.starts_with("down")).unwrap();
+        assert_eq!(down.result, Err("unreachable".to_string()));
+        assert_eq!(sorter.summary().failed, 1);
+    }
+
+    #[test]
+    fn oping_timeout_in_seconds() {
+        assert_eq!(oping_timeout(Duration::from_millis(1500)), 1.5);
+        assert_eq!(oping_timeout(PingConfig::default().timeout), 1.0);
     }
 }